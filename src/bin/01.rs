@@ -1,43 +1,261 @@
-fn parse_input(input: &str) -> Vec<Vec<u32>> {
-    input
-        .lines()
-        .map(|line| line.parse::<u32>().ok())
-        .collect::<Vec<Option<u32>>>()
-        .split(|num| num.is_none())
-        .map(|part| part.iter().map(|num| num.unwrap()).collect())
-        .collect()
-}
-
-fn get_total_counts(input: Vec<Vec<u32>>) -> Vec<u32> {
-    input
+use std::{
+    cmp::Reverse,
+    collections::BinaryHeap,
+    fmt::Write,
+    fs::File,
+    io::{self, BufRead, BufReader},
+    path::PathBuf,
+};
+
+use serde::Serialize;
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+pub struct Elf {
+    // Position of the elf in the input, starting at 0
+    pub index: usize,
+    pub calories: u64,
+}
+
+/// Streams the inventory from `reader` and calls `on_elf` with every elf and the number of items
+/// it carries. Elves are separated by blank lines, repeated blank lines don't add empty elves.
+pub fn read_elves(reader: impl BufRead, mut on_elf: impl FnMut(Elf, usize)) -> io::Result<()> {
+    let mut current: Option<(Elf, usize)> = None;
+    let mut index = 0;
+
+    for (line_number, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line = line.trim();
+
+        if line.is_empty() {
+            if let Some((elf, items)) = current.take() {
+                on_elf(elf, items);
+                index += 1;
+            }
+
+            continue;
+        }
+
+        let calories: u64 = line.parse().map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("invalid calories on line {}: {:?}", line_number + 1, line),
+            )
+        })?;
+
+        let (elf, items) = current.get_or_insert((Elf { index, calories: 0 }, 0));
+        elf.calories += calories;
+        *items += 1;
+    }
+
+    if let Some((elf, items)) = current {
+        on_elf(elf, items);
+    }
+
+    Ok(())
+}
+
+/// Every elf with the number of items it carries, in the order of the input.
+pub fn parse_input(input: &str) -> io::Result<Vec<(Elf, usize)>> {
+    let mut elves = vec![];
+    read_elves(input.as_bytes(), |elf, items| elves.push((elf, items)))?;
+
+    Ok(elves)
+}
+
+/// Streams the inventory from `reader` and returns the `k` elves carrying the most calories,
+/// most calories first. Only `k` elves are kept in memory at any time.
+pub fn top_elves(reader: impl BufRead, k: usize) -> io::Result<Vec<Elf>> {
+    // Min-heap of the best elves so far, ties are resolved in favor of the earlier elf
+    let mut heap: BinaryHeap<Reverse<(u64, Reverse<usize>)>> = BinaryHeap::with_capacity(k + 1);
+
+    read_elves(reader, |elf, _| {
+        heap.push(Reverse((elf.calories, Reverse(elf.index))));
+
+        if heap.len() > k {
+            heap.pop();
+        }
+    })?;
+
+    Ok(heap
+        .into_sorted_vec()
+        .into_iter()
+        .map(|Reverse((calories, Reverse(index)))| Elf { index, calories })
+        .collect())
+}
+
+pub fn part_one(input: &str) -> Option<u64> {
+    let top = top_elves(input.as_bytes(), 1).ok()?;
+
+    top.first().map(|elf| elf.calories)
+}
+
+pub fn part_two(input: &str) -> Option<u64> {
+    let top = top_elves(input.as_bytes(), 3).ok()?;
+
+    Some(top.iter().map(|elf| elf.calories).sum())
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Bucket {
+    // Inclusive range of totals that fall into this bucket
+    pub from: u64,
+    pub to: u64,
+    pub elves: usize,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Stats {
+    pub elves: usize,
+    pub min: u64,
+    pub median: f64,
+    pub mean: f64,
+    pub max: u64,
+    pub items_per_elf: Vec<usize>,
+    pub histogram: Vec<Bucket>,
+}
+
+const HISTOGRAM_BUCKETS: u64 = 10;
+
+pub fn get_stats(elves: &[(Elf, usize)]) -> Option<Stats> {
+    let mut totals = elves
         .iter()
-        .map(|part| part.iter().sum::<u32>())
-        .collect::<Vec<u32>>()
+        .map(|(elf, _)| elf.calories)
+        .collect::<Vec<u64>>();
+    totals.sort_unstable();
+
+    let (&min, &max) = (totals.first()?, totals.last()?);
+    let middle = totals.len() / 2;
+    let median = if totals.len() % 2 == 0 {
+        (totals[middle - 1] as f64 + totals[middle] as f64) / 2.0
+    } else {
+        totals[middle] as f64
+    };
+
+    let width = (max - min) / HISTOGRAM_BUCKETS + 1;
+    let histogram = (0..HISTOGRAM_BUCKETS)
+        .map(|bucket| min + bucket * width)
+        .take_while(|&from| from <= max)
+        .map(|from| Bucket {
+            from,
+            to: from + width - 1,
+            elves: totals
+                .iter()
+                .filter(|&&total| (from..from + width).contains(&total))
+                .count(),
+        })
+        .collect();
+
+    Some(Stats {
+        elves: totals.len(),
+        min,
+        median,
+        mean: totals.iter().sum::<u64>() as f64 / totals.len() as f64,
+        max,
+        items_per_elf: elves.iter().map(|&(_, items)| items).collect(),
+        histogram,
+    })
 }
 
-pub fn part_one(input: &str) -> Option<u32> {
-    let numbers = parse_input(input);
-    let mut totals = get_total_counts(numbers);
-    totals.sort();
+pub fn format_stats(stats: &Stats) -> String {
+    let mut table = String::new();
+
+    writeln!(table, "{:<8} {:>12}", "Elves", stats.elves).unwrap();
+    writeln!(table, "{:<8} {:>12}", "Min", stats.min).unwrap();
+    writeln!(table, "{:<8} {:>12.1}", "Median", stats.median).unwrap();
+    writeln!(table, "{:<8} {:>12.1}", "Mean", stats.mean).unwrap();
+    writeln!(table, "{:<8} {:>12}", "Max", stats.max).unwrap();
+
+    writeln!(table, "\n{:<8} {:>12}", "Elf", "Items").unwrap();
+    for (index, items) in stats.items_per_elf.iter().enumerate() {
+        writeln!(table, "{:<8} {:>12}", format!("#{}", index + 1), items).unwrap();
+    }
+
+    let most = stats
+        .histogram
+        .iter()
+        .map(|bucket| bucket.elves)
+        .max()
+        .unwrap_or(0);
+    writeln!(table, "\n{:>25} {:>6}", "Calories", "Elves").unwrap();
+    for bucket in &stats.histogram {
+        // Bars are scaled so the fullest bucket is 40 characters wide
+        let bar = "#".repeat((bucket.elves * 40).div_ceil(most.max(1)));
+        writeln!(
+            table,
+            "{:>12}-{:<12} {:>6} {}",
+            bucket.from, bucket.to, bucket.elves, bar
+        )
+        .unwrap();
+    }
 
-    totals.last().copied()
+    table
 }
 
-pub fn part_two(input: &str) -> Option<u32> {
-    let numbers = parse_input(input);
-    let mut totals = get_total_counts(numbers);
-    totals.sort();
-    totals.reverse();
+// `--stats` prints inventory statistics (`--json` as JSON), `--top <k>` lists the top k elves and
+// `--input <path>` streams a file other than the puzzle input for them
+fn report() {
+    let mut args = pico_args::Arguments::from_env();
+    let stats = args.contains("--stats");
+    let json = args.contains("--json");
+    let top = args
+        .opt_value_from_str("--top")
+        .and_then(|k| Ok((k, args.opt_value_from_str("--input")?)));
 
-    let (top3, _) = totals.split_at(3);
+    if stats || json {
+        let input = &advent_of_code::read_file("inputs", 1);
+        let stats = match parse_input(input).map(|elves| get_stats(&elves)) {
+            Ok(Some(stats)) => stats,
+            Ok(None) => {
+                eprintln!("No elves found in input");
+                return;
+            }
+            Err(e) => {
+                eprintln!("Invalid inventory: {}", e);
+                return;
+            }
+        };
 
-    Some(top3.iter().sum())
+        if json {
+            println!("{}", serde_json::to_string_pretty(&stats).unwrap());
+        } else {
+            print!("{}", format_stats(&stats));
+        }
+    }
+
+    let (k, path): (Option<usize>, Option<PathBuf>) = match top {
+        Ok(top) => top,
+        Err(e) => {
+            eprintln!("Not listing the top elves: {}", e);
+            return;
+        }
+    };
+
+    if k.is_none() && path.is_none() {
+        return;
+    }
+
+    let k = k.unwrap_or(3);
+    let path = path.unwrap_or_else(|| PathBuf::from("src/inputs/01.txt"));
+    let top = match File::open(&path).and_then(|file| top_elves(BufReader::new(file), k)) {
+        Ok(top) => top,
+        Err(e) => {
+            eprintln!("Failed to read {:?}: {}", path, e);
+            return;
+        }
+    };
+
+    println!("Top {} elves:", k);
+    for elf in &top {
+        println!("Elf #{}: {}", elf.index + 1, elf.calories);
+    }
+    println!("Total: {}", top.iter().map(|elf| elf.calories).sum::<u64>());
 }
 
 fn main() {
     let input = &advent_of_code::read_file("inputs", 1);
     advent_of_code::solve!(1, part_one, input);
     advent_of_code::solve!(2, part_two, input);
+    report();
 }
 
 #[cfg(test)]
@@ -55,4 +273,78 @@ mod tests {
         let input = advent_of_code::read_file("examples", 1);
         assert_eq!(part_two(&input), Some(45000));
     }
+
+    #[test]
+    fn test_top_elves() {
+        let input = advent_of_code::read_file("examples", 1);
+        assert_eq!(
+            top_elves(input.as_bytes(), 2).unwrap(),
+            vec![
+                Elf {
+                    index: 3,
+                    calories: 24000
+                },
+                Elf {
+                    index: 2,
+                    calories: 11000
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn test_stats() {
+        let input = advent_of_code::read_file("examples", 1);
+        let stats = get_stats(&parse_input(&input).unwrap()).unwrap();
+
+        assert_eq!(stats.elves, 5);
+        assert_eq!((stats.min, stats.max), (4000, 24000));
+        assert_eq!(stats.median, 10000.0);
+        assert_eq!(stats.mean, 11000.0);
+        assert_eq!(stats.items_per_elf, vec![3, 1, 2, 3, 1]);
+        assert_eq!(stats.histogram.len(), 10);
+        assert_eq!(
+            stats.histogram[0],
+            Bucket {
+                from: 4000,
+                to: 6000,
+                elves: 2
+            }
+        );
+        assert_eq!(
+            stats
+                .histogram
+                .iter()
+                .map(|bucket| bucket.elves)
+                .sum::<usize>(),
+            5
+        );
+    }
+
+    #[test]
+    fn test_fewer_elves_than_k() {
+        assert_eq!(part_two("5000000000\n\n1"), Some(5000000001));
+        assert_eq!(part_one(""), None);
+        assert!(top_elves("1\nfoo".as_bytes(), 3).is_err());
+    }
+
+    #[test]
+    fn test_malformed_stats() {
+        assert!(parse_input("1\nfoo\n2").is_err());
+
+        // Repeated blank lines don't add empty elves, so both reports number elves alike
+        let input = "\n1\n\n\n\n2\n3\n\n";
+        let stats = get_stats(&parse_input(input).unwrap()).unwrap();
+        let top = top_elves(input.as_bytes(), 1).unwrap();
+
+        assert_eq!((stats.elves, stats.min), (2, 1));
+        assert_eq!(stats.items_per_elf, vec![1, 2]);
+        assert_eq!(
+            top,
+            vec![Elf {
+                index: 1,
+                calories: 5
+            }]
+        );
+    }
 }
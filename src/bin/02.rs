@@ -1,112 +1,409 @@
-#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
-pub enum Choice {
-    Rock,
-    Paper,
-    Scissors,
+use std::fmt::{self, Display, Formatter, Write};
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone)]
+pub struct Shape {
+    pub name: String,
+    // Points for playing this shape
+    pub score: u32,
+    // Letters used for this shape in the strategy guide
+    pub opponent_symbol: char,
+    pub player_symbol: char,
 }
 
-impl From<char> for Choice {
-    fn from(chr: char) -> Self {
-        match chr {
-            'A' | 'X' => Choice::Rock,
-            'B' | 'Y' => Choice::Paper,
-            'C' | 'Z' => Choice::Scissors,
-            _ => panic!("Invalid choice"),
+impl Shape {
+    pub fn new(name: &str, score: u32, opponent_symbol: char, player_symbol: char) -> Self {
+        Self {
+            name: name.to_string(),
+            score,
+            opponent_symbol,
+            player_symbol,
+        }
+    }
+}
+
+/// A game of rock-paper-scissors defined by data: `shapes` form a cycle in which every shape
+/// beats the `beats` shapes that follow it.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone)]
+pub struct Rules {
+    shapes: Vec<Shape>,
+    beats: usize,
+}
+
+// A choice is the index of a shape in `Rules::shapes`
+pub type Choice = usize;
+
+impl Rules {
+    /// Every shape has to beat exactly as many shapes as it loses to, so every result can be
+    /// reached against any shape and no two shapes both lose against each other.
+    pub fn new(shapes: Vec<Shape>, beats: usize) -> Result<Self, String> {
+        if beats == 0 || shapes.len() != 2 * beats + 1 {
+            return Err(format!(
+                "{} shapes can't each beat {} of the others",
+                shapes.len(),
+                beats
+            ));
+        }
+
+        Ok(Self { shapes, beats })
+    }
+
+    pub fn rock_paper_scissors() -> Self {
+        Self::new(
+            vec![
+                Shape::new("Rock", 1, 'A', 'X'),
+                Shape::new("Scissors", 3, 'C', 'Z'),
+                Shape::new("Paper", 2, 'B', 'Y'),
+            ],
+            1,
+        )
+        .unwrap()
+    }
+
+    pub fn rock_paper_scissors_lizard_spock() -> Self {
+        Self::new(
+            vec![
+                Shape::new("Rock", 1, 'A', 'V'),
+                Shape::new("Scissors", 3, 'C', 'X'),
+                Shape::new("Lizard", 4, 'D', 'Y'),
+                Shape::new("Paper", 2, 'B', 'W'),
+                Shape::new("Spock", 5, 'E', 'Z'),
+            ],
+            2,
+        )
+        .unwrap()
+    }
+
+    pub fn opponent_choice(&self, chr: char) -> Option<Choice> {
+        self.shapes
+            .iter()
+            .position(|shape| shape.opponent_symbol == chr)
+    }
+
+    pub fn player_choice(&self, chr: char) -> Option<Choice> {
+        self.shapes
+            .iter()
+            .position(|shape| shape.player_symbol == chr)
+    }
+
+    pub fn shapes(&self) -> &[Shape] {
+        &self.shapes
+    }
+
+    pub fn name(&self, choice: Choice) -> &str {
+        &self.shapes[choice].name
+    }
+
+    /// Result of a game from the point of view of `player`.
+    pub fn outcome(&self, opponent: Choice, player: Choice) -> GameResult {
+        let distance = (opponent + self.shapes.len() - player) % self.shapes.len();
+
+        if distance == 0 {
+            GameResult::Draw
+        } else if distance <= self.beats {
+            GameResult::Win
+        } else {
+            GameResult::Lose
         }
     }
+
+    /// The choice that gives `result` against `opponent`. If several choices do, the one worth
+    /// the most points is played.
+    pub fn choice_for(&self, opponent: Choice, result: GameResult) -> Option<Choice> {
+        (0..self.shapes.len())
+            .filter(|&player| self.outcome(opponent, player) == result)
+            .max_by_key(|&player| self.shapes[player].score)
+    }
 }
 
-#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
 pub enum GameResult {
     Win,
     Lose,
     Draw,
 }
 
-impl From<char> for GameResult {
-    fn from(chr: char) -> Self {
+impl GameResult {
+    pub fn score(&self) -> u32 {
+        match self {
+            GameResult::Win => 6,
+            GameResult::Draw => 3,
+            GameResult::Lose => 0,
+        }
+    }
+}
+
+impl TryFrom<char> for GameResult {
+    type Error = char;
+
+    fn try_from(chr: char) -> Result<Self, Self::Error> {
         match chr {
-            'X' => GameResult::Lose,
-            'Z' => GameResult::Win,
-            'Y' => GameResult::Draw,
-            _ => panic!("Invalid game result"),
+            'X' => Ok(GameResult::Lose),
+            'Z' => Ok(GameResult::Win),
+            'Y' => Ok(GameResult::Draw),
+            _ => Err(chr),
         }
     }
 }
 
-pub fn parse_input(input: &str) -> Vec<(Choice, Choice)> {
-    input
-        .lines()
-        .map(|line| {
-            let instruction = line.chars().collect::<Vec<char>>();
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ParseError {
+    // Line number in the strategy guide, starting at 1
+    pub line: usize,
+    pub content: String,
+    pub reason: String,
+}
 
-            (instruction[0].into(), instruction[2].into())
-        })
-        .collect()
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "line {} ({:?}): {}",
+            self.line, self.content, self.reason
+        )
+    }
 }
 
-pub fn parse_input_by_strategy(input: &str) -> Vec<(Choice, GameResult)> {
+// Splits every line into its two letters, which are then interpreted by `parse`
+fn parse_lines<T>(
+    input: &str,
+    mut parse: impl FnMut(char, char) -> Result<T, String>,
+) -> Result<Vec<T>, ParseError> {
     input
         .lines()
-        .map(|line| {
-            let instruction = line.chars().collect::<Vec<char>>();
+        .enumerate()
+        .map(|(index, line)| {
+            let error = |reason: String| ParseError {
+                line: index + 1,
+                content: line.to_string(),
+                reason,
+            };
 
-            (instruction[0].into(), instruction[2].into())
+            match line.chars().collect::<Vec<char>>()[..] {
+                [first, ' ', second] => parse(first, second).map_err(error),
+                _ => Err(error(
+                    "expected two letters separated by a space".to_string(),
+                )),
+            }
         })
         .collect()
 }
 
-pub fn evalulate_game(game: &(Choice, Choice)) -> u32 {
-    let score_by_type = match game.1 {
-        Choice::Rock => 1,
-        Choice::Paper => 2,
-        Choice::Scissors => 3,
-    };
+fn parse_opponent(rules: &Rules, chr: char) -> Result<Choice, String> {
+    rules
+        .opponent_choice(chr)
+        .ok_or_else(|| format!("invalid opponent choice {:?}", chr))
+}
 
-    let score_by_match = match game {
-        (Choice::Scissors, Choice::Rock) => 6,
-        (Choice::Rock, Choice::Paper) => 6,
-        (Choice::Paper, Choice::Scissors) => 6,
-        _ if game.0 == game.1 => 3,
-        _ => 0,
-    };
+pub fn parse_input(rules: &Rules, input: &str) -> Result<Vec<(Choice, Choice)>, ParseError> {
+    parse_lines(input, |first, second| {
+        let player = rules
+            .player_choice(second)
+            .ok_or_else(|| format!("invalid choice {:?}", second))?;
 
-    score_by_type + score_by_match
+        Ok((parse_opponent(rules, first)?, player))
+    })
 }
 
-pub fn get_game_by_strategy(strategy: &(Choice, GameResult)) -> (Choice, Choice) {
-    match strategy {
-        (Choice::Paper, GameResult::Win) => (strategy.0, Choice::Scissors),
-        (Choice::Rock, GameResult::Win) => (strategy.0, Choice::Paper),
-        (Choice::Scissors, GameResult::Win) => (strategy.0, Choice::Rock),
-        (Choice::Paper, GameResult::Lose) => (strategy.0, Choice::Rock),
-        (Choice::Rock, GameResult::Lose) => (strategy.0, Choice::Scissors),
-        (Choice::Scissors, GameResult::Lose) => (strategy.0, Choice::Paper),
-        (_, GameResult::Draw) => (strategy.0, strategy.0),
-    }
+pub fn parse_input_by_strategy(
+    rules: &Rules,
+    input: &str,
+) -> Result<Vec<(Choice, GameResult)>, ParseError> {
+    parse_lines(input, |first, second| {
+        let result =
+            GameResult::try_from(second).map_err(|chr| format!("invalid game result {:?}", chr))?;
+
+        Ok((parse_opponent(rules, first)?, result))
+    })
 }
 
-pub fn part_one(input: &str) -> Option<u32> {
-    let choices = parse_input(input);
+pub fn evalulate_game(rules: &Rules, game: &(Choice, Choice)) -> u32 {
+    let score_by_type = rules.shapes[game.1].score;
+    let score_by_match = rules.outcome(game.0, game.1).score();
+
+    score_by_type + score_by_match
+}
 
-    Some(choices.iter().map(evalulate_game).sum())
+pub fn get_game_by_strategy(
+    rules: &Rules,
+    strategy: &(Choice, GameResult),
+) -> Option<(Choice, Choice)> {
+    Some((strategy.0, rules.choice_for(strategy.0, strategy.1)?))
 }
 
-pub fn part_two(input: &str) -> Option<u32> {
-    let choices = parse_input_by_strategy(input);
+pub fn part_one(input: &str) -> Option<u32> {
+    let rules = Rules::rock_paper_scissors();
+    let choices = parse_input(&rules, input)
+        .map_err(|e| eprintln!("Invalid strategy guide: {}", e))
+        .ok()?;
+
     Some(
         choices
             .iter()
-            .map(get_game_by_strategy)
-            .map(|game| evalulate_game(&game))
+            .map(|game| evalulate_game(&rules, game))
             .sum(),
     )
 }
 
+pub fn part_two(input: &str) -> Option<u32> {
+    let rules = Rules::rock_paper_scissors();
+    let choices = parse_input_by_strategy(&rules, input)
+        .map_err(|e| eprintln!("Invalid strategy guide: {}", e))
+        .ok()?;
+
+    choices
+        .iter()
+        .map(|strategy| get_game_by_strategy(&rules, strategy))
+        .map(|game| game.map(|game| evalulate_game(&rules, &game)))
+        .sum()
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct Outcomes {
+    pub wins: usize,
+    pub draws: usize,
+    pub losses: usize,
+}
+
+impl Outcomes {
+    fn add(&mut self, result: GameResult) {
+        match result {
+            GameResult::Win => self.wins += 1,
+            GameResult::Draw => self.draws += 1,
+            GameResult::Lose => self.losses += 1,
+        }
+    }
+}
+
+// A single round, played as if the second letter were a choice, a strategy, or always winning
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Round {
+    pub opponent: Choice,
+    pub by_choice: Choice,
+    pub by_strategy: Choice,
+    pub optimal: Choice,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Analysis {
+    pub rounds: Vec<Round>,
+    pub by_choice: Outcomes,
+    pub by_strategy: Outcomes,
+    pub score_by_choice: u32,
+    pub score_by_strategy: u32,
+    pub optimal_score: u32,
+}
+
+pub fn analyze(rules: &Rules, input: &str) -> Result<Analysis, ParseError> {
+    let choices = parse_input(rules, input)?;
+    let strategies = parse_input_by_strategy(rules, input)?;
+
+    // `Rules::new` makes sure every result can be reached
+    let rounds = choices
+        .iter()
+        .zip(strategies.iter())
+        .map(|(game, strategy)| Round {
+            opponent: game.0,
+            by_choice: game.1,
+            by_strategy: rules.choice_for(strategy.0, strategy.1).unwrap(),
+            optimal: rules.choice_for(game.0, GameResult::Win).unwrap(),
+        })
+        .collect::<Vec<Round>>();
+
+    let mut by_choice = Outcomes::default();
+    let mut by_strategy = Outcomes::default();
+    let mut scores = (0, 0, 0);
+
+    for round in &rounds {
+        by_choice.add(rules.outcome(round.opponent, round.by_choice));
+        by_strategy.add(rules.outcome(round.opponent, round.by_strategy));
+
+        scores.0 += evalulate_game(rules, &(round.opponent, round.by_choice));
+        scores.1 += evalulate_game(rules, &(round.opponent, round.by_strategy));
+        scores.2 += evalulate_game(rules, &(round.opponent, round.optimal));
+    }
+
+    Ok(Analysis {
+        rounds,
+        by_choice,
+        by_strategy,
+        score_by_choice: scores.0,
+        score_by_strategy: scores.1,
+        optimal_score: scores.2,
+    })
+}
+
+pub fn format_analysis(rules: &Rules, analysis: &Analysis) -> String {
+    let mut table = String::new();
+    let played = |opponent: Choice, player: Choice| {
+        format!(
+            "{:<9} {:<5} {:>5}",
+            rules.name(player),
+            format!("{:?}", rules.outcome(opponent, player)),
+            evalulate_game(rules, &(opponent, player))
+        )
+    };
+
+    writeln!(
+        table,
+        "{:>5}  {:<9}  {:<21}  {:<21}  {:<21}",
+        "Round", "Opponent", "By choice", "By strategy", "Optimal"
+    )
+    .unwrap();
+
+    for (index, round) in analysis.rounds.iter().enumerate() {
+        writeln!(
+            table,
+            "{:>5}  {:<9}  {}  {}  {}",
+            index + 1,
+            rules.name(round.opponent),
+            played(round.opponent, round.by_choice),
+            played(round.opponent, round.by_strategy),
+            played(round.opponent, round.optimal),
+        )
+        .unwrap();
+    }
+
+    writeln!(table).unwrap();
+    for (label, outcomes, score) in [
+        ("By choice", analysis.by_choice, analysis.score_by_choice),
+        (
+            "By strategy",
+            analysis.by_strategy,
+            analysis.score_by_strategy,
+        ),
+    ] {
+        writeln!(
+            table,
+            "{:<12} {:>6} wins {:>6} draws {:>6} losses, score {}",
+            label, outcomes.wins, outcomes.draws, outcomes.losses, score
+        )
+        .unwrap();
+    }
+    writeln!(
+        table,
+        "{:<12} {:>6} wins, score {}",
+        "Optimal",
+        analysis.rounds.len(),
+        analysis.optimal_score
+    )
+    .unwrap();
+
+    table
+}
+
 fn main() {
     let input = &advent_of_code::read_file("inputs", 2);
     advent_of_code::solve!(1, part_one, input);
     advent_of_code::solve!(2, part_two, input);
+
+    if pico_args::Arguments::from_env().contains("--analyze") {
+        let rules = Rules::rock_paper_scissors();
+
+        match analyze(&rules, input) {
+            Ok(analysis) => print!("{}", format_analysis(&rules, &analysis)),
+            Err(e) => eprintln!("Invalid strategy guide: {}", e),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -124,4 +421,96 @@ mod tests {
         let input = advent_of_code::read_file("examples", 2);
         assert_eq!(part_two(&input), Some(12));
     }
+
+    #[test]
+    fn test_analyze() {
+        let rules = Rules::rock_paper_scissors();
+        let input = advent_of_code::read_file("examples", 2);
+        let analysis = analyze(&rules, &input).unwrap();
+
+        assert_eq!(
+            analysis.by_choice,
+            Outcomes {
+                wins: 1,
+                draws: 1,
+                losses: 1
+            }
+        );
+        assert_eq!(
+            analysis.by_strategy,
+            Outcomes {
+                wins: 1,
+                draws: 1,
+                losses: 1
+            }
+        );
+        assert_eq!(analysis.score_by_choice, 15);
+        assert_eq!(analysis.score_by_strategy, 12);
+        // Paper, Scissors and Rock against Rock, Paper and Scissors
+        assert_eq!(analysis.optimal_score, 8 + 9 + 7);
+    }
+
+    #[test]
+    fn test_invalid_line() {
+        let rules = Rules::rock_paper_scissors();
+
+        assert_eq!(
+            parse_input(&rules, "A Y\nA Q").unwrap_err(),
+            ParseError {
+                line: 2,
+                content: "A Q".to_string(),
+                reason: "invalid choice 'Q'".to_string()
+            }
+        );
+        assert_eq!(part_two("AZ"), None);
+    }
+
+    #[test]
+    fn test_lizard_spock() {
+        let rules = Rules::rock_paper_scissors_lizard_spock();
+        let games = parse_input(&rules, "A Y\nE X\nD W\nB V").unwrap();
+
+        // Rock crushes Lizard, Spock smashes Scissors, Lizard eats Paper, Paper covers Rock
+        assert_eq!(
+            games
+                .iter()
+                .map(|game| rules.outcome(game.0, game.1))
+                .collect::<Vec<GameResult>>(),
+            vec![
+                GameResult::Lose,
+                GameResult::Lose,
+                GameResult::Lose,
+                GameResult::Lose
+            ]
+        );
+
+        // Against Rock, Paper and Spock both win and Spock is worth more
+        let rock = rules.opponent_choice('A').unwrap();
+        let spock = rules.player_choice('Z').unwrap();
+        assert_eq!(rules.choice_for(rock, GameResult::Win), Some(spock));
+        assert_eq!(evalulate_game(&rules, &(rock, spock)), 11);
+    }
+
+    #[test]
+    fn test_invalid_rules() {
+        let shapes = Rules::rock_paper_scissors().shapes().to_vec();
+
+        assert!(Rules::new(shapes.clone(), 0).is_err());
+        assert_eq!(
+            Rules::new(shapes.clone(), 2).unwrap_err(),
+            "3 shapes can't each beat 2 of the others"
+        );
+        assert_eq!(
+            Rules::new(shapes.clone(), 1),
+            Ok(Rules::rock_paper_scissors())
+        );
+
+        // With 4 shapes, Rock and Paper would both lose against each other
+        let mut four = shapes;
+        four.push(Shape::new("Well", 4, 'D', 'W'));
+        assert_eq!(
+            Rules::new(four, 1).unwrap_err(),
+            "4 shapes can't each beat 1 of the others"
+        );
+    }
 }
@@ -1,92 +1,208 @@
-use std::collections::HashSet;
+use std::fmt::{self, Display, Formatter};
 
-// NOTE: this is terrible code, I got frustrated near the end (thanks rustc!) and just decided that I didn't give a shit about code quality
-// You've been warned
+// A set of item types, bit `n` is set if the item with priority `n` is present
+pub type Items = u64;
 
-pub fn parse_input(input: &str) -> Vec<(Vec<char>, Vec<char>)> {
-    input
-        .lines()
-        .map(|line| {
-            let (first, second) = line.split_at(line.len() / 2);
+pub fn get_priority(item: char) -> Option<u32> {
+    match item {
+        'a'..='z' => Some(item as u32 - 'a' as u32 + 1),
+        'A'..='Z' => Some(item as u32 - 'A' as u32 + 27),
+        _ => None,
+    }
+}
 
-            (first.chars().collect(), second.chars().collect())
-        })
+pub fn get_item(priority: u32) -> char {
+    match priority {
+        1..=26 => (b'a' + priority as u8 - 1) as char,
+        27..=52 => (b'A' + priority as u8 - 27) as char,
+        _ => panic!("Invalid priority {}", priority),
+    }
+}
+
+pub fn items_of(items: Items) -> Vec<char> {
+    (1..=52)
+        .filter(|priority| items & (1 << priority) != 0)
+        .map(get_item)
         .collect()
 }
 
-pub fn find_shared(input: &(Vec<char>, Vec<char>)) -> Vec<char> {
-    let first: HashSet<&char> = HashSet::from_iter(input.0.iter());
-    let second: HashSet<&char> = HashSet::from_iter(input.1.iter());
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum RucksackError {
+    InvalidItem { line: usize, item: char },
+    OddLength { line: usize, length: usize },
+    // For shared items, `line` is the rucksack or the first rucksack of its group
+    NoSharedItem { line: usize },
+    MultipleSharedItems { line: usize, items: Vec<char> },
+    IncompleteGroup { line: usize, size: usize },
+    EmptyGroup,
+}
 
-    first.intersection(&second).map(|&&c| c).collect()
+impl Display for RucksackError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            RucksackError::InvalidItem { line, item } => {
+                write!(f, "line {}: invalid item {:?}", line, item)
+            }
+            RucksackError::OddLength { line, length } => write!(
+                f,
+                "line {}: {} items can't be split into two compartments",
+                line, length
+            ),
+            RucksackError::NoSharedItem { line } => {
+                write!(f, "line {}: no item is shared", line)
+            }
+            RucksackError::MultipleSharedItems { line, items } => write!(
+                f,
+                "line {}: more than one item is shared ({})",
+                line,
+                items.iter().collect::<String>()
+            ),
+            RucksackError::IncompleteGroup { line, size } => {
+                write!(f, "line {}: group only has {} rucksacks", line, size)
+            }
+            RucksackError::EmptyGroup => write!(f, "groups need at least one rucksack"),
+        }
+    }
 }
 
-pub fn get_priority(input: char) -> u32 {
-    let mut alphabet = ('a'..='z').collect::<Vec<char>>();
-    alphabet.extend('A'..='Z');
+fn parse_items(line: usize, items: &[char]) -> Result<Items, RucksackError> {
+    items.iter().try_fold(0, |set, item| {
+        get_priority(*item)
+            .map(|priority| set | 1 << priority)
+            .ok_or(RucksackError::InvalidItem { line, item: *item })
+    })
+}
 
-    alphabet.iter().position(|&l| l == input).unwrap() as u32 + 1
+// Priority of the only item in `items`
+fn single_item(line: usize, items: Items) -> Result<u32, RucksackError> {
+    match items.count_ones() {
+        0 => Err(RucksackError::NoSharedItem { line }),
+        1 => Ok(items.trailing_zeros()),
+        _ => Err(RucksackError::MultipleSharedItems {
+            line,
+            items: items_of(items),
+        }),
+    }
 }
 
-pub fn part_one(input: &str) -> Option<u32> {
-    let parsed = parse_input(input);
-    Some(
-        parsed
-            .iter()
-            .map(find_shared)
-            .map(|sack| sack.iter().map(|c| get_priority(*c)).sum::<u32>())
-            .sum(),
-    )
-}
-
-pub fn parse_input_part_2(input: &str) -> Vec<(Vec<char>, Vec<char>, Vec<char>)> {
-    let a = input
-        .lines()
-        .collect::<Vec<&str>>()
-        .chunks(3)
-        .map(|c| c.iter().map(|&s| s.chars().collect()).collect())
-        .collect::<Vec<Vec<Vec<char>>>>();
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Rucksack {
+    // Line of the rucksack in the input, starting at 1
+    pub line: usize,
+    pub first: Items,
+    pub second: Items,
+}
 
-    let b = a
-        .iter()
-        .map(|c| (c[0].clone(), c[1].clone(), c[2].clone()))
-        .collect::<Vec<_>>();
+impl Rucksack {
+    pub fn items(&self) -> Items {
+        self.first | self.second
+    }
 
-    b
+    pub fn shared(&self) -> Items {
+        self.first & self.second
+    }
 }
 
-pub fn find_shared_part_2(input: &(Vec<char>, Vec<char>, Vec<char>)) -> Vec<char> {
-    let sets = vec![
-        HashSet::from_iter(input.0.iter()),
-        HashSet::from_iter(input.1.iter()),
-        HashSet::from_iter(input.2.iter()),
-    ];
+pub fn parse_input(input: &str) -> Result<Vec<Rucksack>, RucksackError> {
+    input
+        .lines()
+        .enumerate()
+        .map(|(index, line)| {
+            let number = index + 1;
+            let items = line.chars().collect::<Vec<char>>();
+
+            if items.len() % 2 != 0 {
+                return Err(RucksackError::OddLength {
+                    line: number,
+                    length: items.len(),
+                });
+            }
 
-    let new = sets
+            let (first, second) = items.split_at(items.len() / 2);
+
+            Ok(Rucksack {
+                line: number,
+                first: parse_items(number, first)?,
+                second: parse_items(number, second)?,
+            })
+        })
+        .collect()
+}
+
+/// Priority of the item found in both compartments of every rucksack.
+pub fn shared_priorities(rucksacks: &[Rucksack]) -> Result<Vec<u32>, RucksackError> {
+    rucksacks
         .iter()
-        .skip(1)
-        .fold(sets[0].clone(), |a: HashSet<&char>, b| {
-            a.intersection(b).cloned().collect()
-        });
+        .map(|rucksack| single_item(rucksack.line, rucksack.shared()))
+        .collect()
+}
+
+/// Priority of the badge shared by every group of `size` consecutive rucksacks.
+pub fn badge_priorities(rucksacks: &[Rucksack], size: usize) -> Result<Vec<u32>, RucksackError> {
+    if size == 0 {
+        return Err(RucksackError::EmptyGroup);
+    }
+
+    rucksacks
+        .chunks(size)
+        .map(|group| {
+            let line = group[0].line;
 
-    new.iter().map(|&&c| c).collect()
+            if group.len() < size {
+                return Err(RucksackError::IncompleteGroup {
+                    line,
+                    size: group.len(),
+                });
+            }
+
+            let shared = group
+                .iter()
+                .fold(Items::MAX, |shared, rucksack| shared & rucksack.items());
+
+            single_item(line, shared)
+        })
+        .collect()
+}
+
+fn sum_priorities(
+    input: &str,
+    priorities: impl FnOnce(&[Rucksack]) -> Result<Vec<u32>, RucksackError>,
+) -> Option<u32> {
+    parse_input(input)
+        .and_then(|rucksacks| priorities(&rucksacks))
+        .map(|priorities| priorities.iter().sum())
+        .map_err(|e| eprintln!("Invalid rucksacks: {}", e))
+        .ok()
+}
+
+pub fn part_one(input: &str) -> Option<u32> {
+    sum_priorities(input, shared_priorities)
 }
 
 pub fn part_two(input: &str) -> Option<u32> {
-    let parsed = parse_input_part_2(input);
-    Some(
-        parsed
-            .iter()
-            .map(find_shared_part_2)
-            .map(|s| s.iter().map(|c| get_priority(*c)).sum::<u32>())
-            .sum::<u32>(),
-    )
+    sum_priorities(input, |rucksacks| badge_priorities(rucksacks, 3))
 }
 
 fn main() {
     let input = &advent_of_code::read_file("inputs", 3);
     advent_of_code::solve!(1, part_one, input);
     advent_of_code::solve!(2, part_two, input);
+
+    // `--group-size <n>` also finds the badges of groups of n elves
+    let mut args = pico_args::Arguments::from_env();
+    let size: Option<usize> = match args.opt_value_from_str("--group-size") {
+        Ok(size) => size,
+        Err(e) => {
+            eprintln!("Invalid group size: {}", e);
+            return;
+        }
+    };
+
+    if let Some(size) = size {
+        if let Some(sum) = sum_priorities(input, |rucksacks| badge_priorities(rucksacks, size)) {
+            println!("Badges of groups of {}: {}", size, sum);
+        }
+    }
 }
 
 #[cfg(test)]
@@ -104,4 +220,54 @@ mod tests {
         let input = advent_of_code::read_file("examples", 3);
         assert_eq!(part_two(&input), Some(70));
     }
+
+    #[test]
+    fn test_priority() {
+        assert_eq!(get_priority('a'), Some(1));
+        assert_eq!(get_priority('Z'), Some(52));
+        assert_eq!(get_priority('1'), None);
+        assert!(('a'..='z')
+            .chain('A'..='Z')
+            .all(|item| get_item(get_priority(item).unwrap()) == item));
+    }
+
+    #[test]
+    fn test_group_size() {
+        let rucksacks = parse_input("abcX\nXdef\nghXi\njXkl").unwrap();
+
+        assert_eq!(badge_priorities(&rucksacks, 2), Ok(vec![50, 50]));
+        assert_eq!(badge_priorities(&rucksacks, 4), Ok(vec![50]));
+        assert_eq!(
+            badge_priorities(&rucksacks, 3),
+            Err(RucksackError::IncompleteGroup { line: 4, size: 1 })
+        );
+        assert_eq!(
+            badge_priorities(&rucksacks, 1),
+            Err(RucksackError::MultipleSharedItems {
+                line: 1,
+                items: vec!['a', 'b', 'c', 'X']
+            })
+        );
+        assert_eq!(
+            badge_priorities(&rucksacks, 0),
+            Err(RucksackError::EmptyGroup)
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            parse_input("abca\nabc"),
+            Err(RucksackError::OddLength { line: 2, length: 3 })
+        );
+        assert_eq!(
+            parse_input("ab-a"),
+            Err(RucksackError::InvalidItem { line: 1, item: '-' })
+        );
+        assert_eq!(
+            shared_priorities(&parse_input("abca\nabcd").unwrap()),
+            Err(RucksackError::NoSharedItem { line: 2 })
+        );
+        assert_eq!(part_one("abab"), None);
+    }
 }
@@ -1,4 +1,53 @@
-pub fn parse_input(input: &str) -> Vec<(Vec<u32>, Vec<u32>)> {
+use std::fmt::{self, Display, Formatter};
+
+/// An inclusive range of section IDs. Counts of sections are `u128`, as a range can span all of
+/// `u64`.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+pub struct Assignment {
+    pub start: u64,
+    pub end: u64,
+}
+
+impl Assignment {
+    /// Reversed bounds like `9-3` are swapped, so the assignment is still `3-9`.
+    pub fn new(start: u64, end: u64) -> Self {
+        Self {
+            start: start.min(end),
+            end: start.max(end),
+        }
+    }
+
+    pub fn sections(&self) -> u128 {
+        (self.end - self.start) as u128 + 1
+    }
+
+    pub fn contains(&self, other: &Assignment) -> bool {
+        self.start <= other.start && other.end <= self.end
+    }
+
+    pub fn overlaps(&self, other: &Assignment) -> bool {
+        self.start <= other.end && other.start <= self.end
+    }
+
+    /// Number of sections in both assignments.
+    pub fn overlap(&self, other: &Assignment) -> u128 {
+        if self.overlaps(other) {
+            Assignment::new(self.start.max(other.start), self.end.min(other.end)).sections()
+        } else {
+            0
+        }
+    }
+}
+
+impl Display for Assignment {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}", self.start, self.end)
+    }
+}
+
+pub type Pair = (Assignment, Assignment);
+
+pub fn parse_input(input: &str) -> Vec<Pair> {
     input
         .lines()
         .map(|line| {
@@ -6,36 +55,70 @@ pub fn parse_input(input: &str) -> Vec<(Vec<u32>, Vec<u32>)> {
                 .split(',')
                 .map(|range| {
                     let range = range.split('-').collect::<Vec<&str>>();
-                    let begin: u32 = range[0].parse().unwrap();
-                    let end: u32 = range[1].parse().unwrap();
+                    let begin: u64 = range[0].parse().unwrap();
+                    let end: u64 = range[1].parse().unwrap();
 
-                    (begin..=end).collect::<Vec<u32>>()
+                    Assignment::new(begin, end)
                 })
-                .collect::<Vec<Vec<u32>>>();
+                .collect::<Vec<Assignment>>();
 
-            (arr[0].clone(), arr[1].clone())
+            (arr[0], arr[1])
         })
         .collect()
 }
 
-fn find_overlapping_pairs(input: &Vec<(Vec<u32>, Vec<u32>)>) -> Vec<&(Vec<u32>, Vec<u32>)> {
+fn find_overlapping_pairs(input: &[Pair]) -> Vec<&Pair> {
     input
         .iter()
-        .filter(|pair| {
-            pair.0.iter().all(|x| pair.1.contains(x)) || pair.1.iter().all(|x| pair.0.contains(x))
-        })
+        .filter(|pair| pair.0.contains(&pair.1) || pair.1.contains(&pair.0))
         .collect()
 }
 
-fn find_any_overlapping_pairs(input: &Vec<(Vec<u32>, Vec<u32>)>) -> Vec<&(Vec<u32>, Vec<u32>)> {
+fn find_any_overlapping_pairs(input: &[Pair]) -> Vec<&Pair> {
     input
         .iter()
-        .filter(|pair| {
-            pair.0.iter().any(|x| pair.1.contains(x)) || pair.1.iter().any(|x| pair.0.contains(x))
-        })
+        .filter(|pair| pair.0.overlaps(&pair.1))
         .collect()
 }
 
+pub fn find_disjoint_pairs(input: &[Pair]) -> Vec<&Pair> {
+    input
+        .iter()
+        .filter(|pair| !pair.0.overlaps(&pair.1))
+        .collect()
+}
+
+pub fn overlap_lengths(input: &[Pair]) -> Vec<u128> {
+    input.iter().map(|pair| pair.0.overlap(&pair.1)).collect()
+}
+
+/// Sections between the lowest and the highest assigned section that nobody is assigned to.
+pub fn uncovered_sections(input: &[Pair]) -> Vec<Assignment> {
+    let mut assignments = input
+        .iter()
+        .flat_map(|pair| [pair.0, pair.1])
+        .collect::<Vec<Assignment>>();
+    assignments.sort_unstable();
+
+    let mut gaps = vec![];
+    let mut covered_until: Option<u64> = None;
+
+    for assignment in assignments {
+        match covered_until {
+            // Nothing can be left uncovered after the last section
+            Some(u64::MAX) => break,
+            Some(end) if assignment.start > end + 1 => {
+                gaps.push(Assignment::new(end + 1, assignment.start - 1));
+            }
+            _ => {}
+        }
+
+        covered_until = Some(covered_until.map_or(assignment.end, |end| end.max(assignment.end)));
+    }
+
+    gaps
+}
+
 pub fn part_one(input: &str) -> Option<u32> {
     let parsed = parse_input(input);
     let overlapping = find_overlapping_pairs(&parsed);
@@ -54,6 +137,27 @@ fn main() {
     let input = &advent_of_code::read_file("inputs", 4);
     advent_of_code::solve!(1, part_one, input);
     advent_of_code::solve!(2, part_two, input);
+
+    // `--report` lists the overlap of every pair, the pairs that don't overlap and the sections
+    // nobody has to clean
+    if pico_args::Arguments::from_env().contains("--report") {
+        let pairs = parse_input(input);
+
+        println!("Overlap per pair:");
+        for (pair, overlap) in pairs.iter().zip(overlap_lengths(&pairs)) {
+            println!("{},{}: {}", pair.0, pair.1, overlap);
+        }
+
+        println!("Pairs without overlap:");
+        for pair in find_disjoint_pairs(&pairs) {
+            println!("{},{}", pair.0, pair.1);
+        }
+
+        println!("Sections covered by nobody:");
+        for gap in uncovered_sections(&pairs) {
+            println!("{} ({} sections)", gap, gap.sections());
+        }
+    }
 }
 
 #[cfg(test)]
@@ -71,4 +175,41 @@ mod tests {
         let input = advent_of_code::read_file("examples", 4);
         assert_eq!(part_two(&input), Some(4));
     }
+
+    #[test]
+    fn test_queries() {
+        let input = advent_of_code::read_file("examples", 4);
+        let pairs = parse_input(&input);
+
+        assert_eq!(overlap_lengths(&pairs), vec![0, 0, 1, 5, 1, 3]);
+        assert_eq!(find_disjoint_pairs(&pairs), vec![&pairs[0], &pairs[1]]);
+        assert_eq!(uncovered_sections(&pairs), vec![]);
+    }
+
+    #[test]
+    fn test_huge_ranges() {
+        let pairs = parse_input("1-4000000000,5-10\n4000000005-18000000000000000000,1-2");
+
+        assert_eq!(part_one("1-4000000000,5-10"), Some(1));
+        assert_eq!(overlap_lengths(&pairs), vec![6, 0]);
+        assert_eq!(
+            uncovered_sections(&pairs),
+            vec![Assignment::new(4000000001, 4000000004)]
+        );
+
+        let full = "0-18446744073709551615,0-18446744073709551615\n5-6,10-18446744073709551615";
+        let pairs = parse_input(full);
+
+        assert_eq!(pairs[0].0.sections(), u64::MAX as u128 + 1);
+        assert_eq!(overlap_lengths(&pairs), vec![u64::MAX as u128 + 1, 0]);
+        assert_eq!(uncovered_sections(&pairs), vec![]);
+        assert_eq!(
+            uncovered_sections(&parse_input("0-18446744073709551615,5-6")),
+            vec![]
+        );
+        assert_eq!(
+            uncovered_sections(&parse_input("9-3,18446744073709551615-12")),
+            vec![Assignment::new(10, 11)]
+        );
+    }
 }
@@ -1,102 +1,400 @@
-use std::collections::LinkedList;
+use std::fmt::{self, Display, Formatter};
 
-type State = Vec<LinkedList<char>>;
-type Instructions = Vec<(u32, u32, u32)>;
+use advent_of_code::helpers::animate::{AnimateArgs, Animator};
 
-pub fn parse_input(input: &str) -> (State, Instructions) {
-    let lines: Vec<&str> = input.lines().collect();
-    let sections = lines.split(|&line| line == "").collect::<Vec<&[&str]>>();
+// Crates from the bottom to the top of the stack
+type Stack = Vec<char>;
+type State = Vec<Stack>;
+// Amount of crates, and the stacks (starting at 1) they are moved from and to
+type Instruction = (u32, u32, u32);
+type Instructions = Vec<Instruction>;
 
-    let initial_section = sections[0];
-    let instructions_section = sections[1];
+// Columns spanned by every label in the label row, e.g. ` 1   2 ` gives [(1, 1), (5, 5)]
+fn label_columns(labels: &str) -> Result<Vec<(usize, usize)>, String> {
+    let chars = labels.chars().collect::<Vec<char>>();
+    let mut columns = vec![];
+    let mut column = 0;
 
-    let mut initial_rows = initial_section
-        .iter()
-        .map(|line| {
-            line.chars()
+    while column < chars.len() {
+        if chars[column].is_whitespace() {
+            column += 1;
+            continue;
+        }
+
+        let start = column;
+        while column < chars.len() && !chars[column].is_whitespace() {
+            column += 1;
+        }
+
+        let label = chars[start..column].iter().collect::<String>();
+        if label.parse::<usize>() != Ok(columns.len() + 1) {
+            return Err(format!(
+                "expected stack label {}, found {:?}",
+                columns.len() + 1,
+                label
+            ));
+        }
+
+        columns.push((start, column - 1));
+    }
+
+    Ok(columns)
+}
+
+/// Parses a drawing of the stacks. Crates are assigned to the label closest to them, so labels
+/// of any width and missing trailing whitespace are fine.
+pub fn parse_diagram(lines: &[&str]) -> Result<State, String> {
+    let (labels, rows) = lines.split_last().ok_or("missing stack diagram")?;
+    let columns = label_columns(labels)?;
+    let mut state: State = vec![vec![]; columns.len()];
+
+    for row in rows.iter().rev() {
+        let chars = row.chars().collect::<Vec<char>>();
+
+        for (column, window) in chars.windows(3).enumerate() {
+            if window[0] != '[' || window[2] != ']' {
+                continue;
+            }
+
+            let column = column + 1;
+            let (stack, distance) = columns
+                .iter()
+                .map(|&(start, end)| start.saturating_sub(column) + column.saturating_sub(end))
                 .enumerate()
-                .filter(|(i, _)| (i + 1) % 4 != 0)
-                .map(|(_, c)| c)
-                .collect::<Vec<char>>()
-                .chunks(3)
-                .map(|chunk| {
-                    if chunk[1] == ' ' {
-                        None
-                    } else {
-                        Some(chunk[1])
-                    }
+                .min_by_key(|&(_, distance)| distance)
+                .ok_or("missing stack labels")?;
+
+            if distance > 1 {
+                return Err(format!("crate {} is not above a stack label", window[1]));
+            }
+
+            state[stack].push(window[1]);
+        }
+    }
+
+    Ok(state)
+}
+
+/// Draws `state` in the same format as the puzzle input, without trailing whitespace.
+pub fn render(state: &State) -> String {
+    let height = state.iter().map(|stack| stack.len()).max().unwrap_or(0);
+    let mut lines = (0..height)
+        .rev()
+        .map(|level| {
+            state
+                .iter()
+                .map(|stack| match stack.get(level) {
+                    Some(item) => format!("[{}]", item),
+                    None => "   ".to_string(),
                 })
-                .collect::<Vec<Option<char>>>()
+                .collect::<Vec<String>>()
+                .join(" ")
         })
-        .collect::<Vec<Vec<Option<char>>>>();
+        .collect::<Vec<String>>();
 
-    initial_rows.reverse();
+    // Labels end in the column of the crates above them, so two digit labels still line up
+    lines.push(
+        (1..=state.len())
+            .map(|label| format!("{:>2}  ", label))
+            .collect::<String>(),
+    );
 
-    let column_count = initial_rows[0].len();
-    let mut state: Vec<LinkedList<char>> = (0..column_count).map(|_| LinkedList::new()).collect();
+    lines
+        .iter()
+        .map(|line| line.trim_end())
+        .collect::<Vec<&str>>()
+        .join("\n")
+}
 
-    initial_rows.iter().skip(1).for_each(|row| {
-        row.iter()
-            .enumerate()
-            .filter(|(_, item)| item.is_some())
-            .for_each(|(i, item)| {
-                state[i].push_front(item.unwrap());
-            });
-    });
+pub fn parse_input(input: &str) -> Result<(State, Instructions), String> {
+    let lines: Vec<&str> = input.lines().collect();
+    let sections = lines
+        .split(|line| line.trim().is_empty())
+        .filter(|section| !section.is_empty())
+        .collect::<Vec<&[&str]>>();
+
+    let state = parse_diagram(sections.first().copied().unwrap_or_default())?;
 
-    let instructions = instructions_section
+    let instructions = sections
+        .get(1)
+        .copied()
+        .unwrap_or_default()
         .iter()
         .map(|line| {
-            line.split(' ')
-                .map(|chr| chr.parse::<u32>())
-                .filter(|result| result.is_ok())
-                .map(|result| result.unwrap())
-                .collect::<Vec<u32>>()
+            match line
+                .split_whitespace()
+                .filter_map(|chr| chr.parse::<u32>().ok())
+                .collect::<Vec<u32>>()[..]
+            {
+                [amount, from, to] => Ok((amount, from, to)),
+                _ => Err(format!("invalid instruction {:?}", line)),
+            }
         })
-        .map(|arr| (arr[0], arr[1], arr[2]))
-        .collect::<Vec<(u32, u32, u32)>>();
+        .collect::<Result<Instructions, String>>()?;
 
-    (state, instructions)
+    Ok((state, instructions))
 }
 
-pub fn part_one(input: &str) -> Option<String> {
-    let (mut state, instructions) = parse_input(input);
+type FrameCallback<'a> = &'a mut dyn FnMut(&State);
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct MoveError {
+    // Position of the instruction, starting at 1
+    pub index: usize,
+    pub instruction: Instruction,
+    // Stacks right before the instruction
+    pub state: State,
+    pub reason: String,
+}
+
+impl Display for MoveError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let (amount, from, to) = self.instruction;
+        let stacks = self
+            .state
+            .iter()
+            .enumerate()
+            .map(|(i, stack)| format!("{}: {}", i + 1, stack.iter().collect::<String>()))
+            .collect::<Vec<String>>();
+
+        write!(
+            f,
+            "instruction {} (move {} from {} to {}): {}, stacks are [{}]",
+            self.index,
+            amount,
+            from,
+            to,
+            self.reason,
+            stacks.join(", ")
+        )
+    }
+}
+
+/// A crane model. Crates are lifted off the top of a stack in batches of at most `capacity`,
+/// every batch keeps its order.
+pub trait Crane {
+    fn capacity(&self) -> usize;
+
+    /// Moves `amount` crates between the stacks at `from` and `to`, which are known to exist.
+    /// The crane has to be able to lift at least one crate.
+    fn move_crates(&self, state: &mut State, amount: usize, from: usize, to: usize) {
+        assert!(
+            self.capacity() > 0,
+            "a crane has to lift at least one crate"
+        );
+        let mut remaining = amount;
+
+        while remaining > 0 {
+            let batch = remaining.min(self.capacity());
+            let source = &mut state[from];
+            let lifted = source.split_off(source.len() - batch);
 
-    instructions.iter().for_each(|(amount, from, to)| {
-        for _ in 0..*amount {
-            let item = state[(from - 1) as usize].pop_front().unwrap();
-            state[(to - 1) as usize].push_front(item);
+            state[to].extend(lifted);
+            remaining -= batch;
         }
-    });
+    }
+}
+
+// CrateMover 9000: crates are moved one at a time
+pub struct CrateMover9000;
 
-    let chars: String = state.iter().map(|list| *list.front().unwrap()).collect();
+impl Crane for CrateMover9000 {
+    fn capacity(&self) -> usize {
+        1
+    }
+}
 
-    Some(chars)
+// CrateMover 9001: all crates of an instruction are moved at once
+pub struct CrateMover9001;
+
+impl Crane for CrateMover9001 {
+    fn capacity(&self) -> usize {
+        usize::MAX
+    }
 }
 
-pub fn part_two(input: &str) -> Option<String> {
-    let (mut state, instructions) = parse_input(input);
+// A crane that lifts at most the given number of crates at once
+pub struct LimitedCrane(pub usize);
+
+impl Crane for LimitedCrane {
+    fn capacity(&self) -> usize {
+        self.0
+    }
+}
+
+pub fn run(
+    crane: &dyn Crane,
+    state: &mut State,
+    instructions: &Instructions,
+    mut on_frame: Option<FrameCallback>,
+) -> Result<(), MoveError> {
+    for (index, &instruction) in instructions.iter().enumerate() {
+        let (amount, from, to) = instruction;
+        let error = |state: &State, reason: String| MoveError {
+            index: index + 1,
+            instruction,
+            state: state.clone(),
+            reason,
+        };
 
-    instructions.iter().for_each(|(amount, from, to)| {
-        let mut pulled = (0..*amount)
-            .map(|_| state[(from - 1) as usize].pop_front().unwrap())
-            .collect::<Vec<char>>();
-        pulled.reverse();
+        for stack in [from, to] {
+            if stack == 0 || stack as usize > state.len() {
+                return Err(error(state, format!("there is no stack {}", stack)));
+            }
+        }
 
-        for i in 0..*amount {
-            state[(to - 1) as usize].push_front(pulled[i as usize]);
+        if amount > 0 && crane.capacity() == 0 {
+            return Err(error(state, "the crane can't lift any crates".to_string()));
         }
-    });
 
-    let chars: String = state.iter().map(|list| *list.front().unwrap()).collect();
+        let available = state[(from - 1) as usize].len();
+        if amount as usize > available {
+            return Err(error(
+                state,
+                format!(
+                    "stack {} only holds {} crates, {} requested",
+                    from, available, amount
+                ),
+            ));
+        }
 
-    Some(chars)
+        crane.move_crates(
+            state,
+            amount as usize,
+            (from - 1) as usize,
+            (to - 1) as usize,
+        );
+
+        if let Some(on_frame) = on_frame.as_mut() {
+            on_frame(state);
+        }
+    }
+
+    Ok(())
+}
+
+pub fn top_crates(state: &State) -> String {
+    state.iter().filter_map(|stack| stack.last()).collect()
+}
+
+fn solve_with(crane: &dyn Crane, input: &str) -> Option<String> {
+    let (mut state, instructions) = parse_input(input)
+        .map_err(|e| eprintln!("Invalid input: {}", e))
+        .ok()?;
+
+    run(crane, &mut state, &instructions, None)
+        .map_err(|e| eprintln!("Illegal move: {}", e))
+        .ok()?;
+
+    Some(top_crates(&state))
+}
+
+pub fn part_one(input: &str) -> Option<String> {
+    solve_with(&CrateMover9000, input)
+}
+
+pub fn part_two(input: &str) -> Option<String> {
+    solve_with(&CrateMover9001, input)
+}
+
+// Every crate is drawn as `[X]` above its stack label
+fn cells(state: &State) -> Vec<((i64, i64), char)> {
+    state
+        .iter()
+        .enumerate()
+        .flat_map(|(i, stack)| {
+            let x = i as i64 * 4;
+            // Like `render`, labels end in the column of the crates
+            let label = (i + 1).to_string();
+            let start = x + 2 - label.len() as i64;
+            let label = label
+                .chars()
+                .enumerate()
+                .map(|(offset, digit)| ((start + offset as i64, 0), digit))
+                .collect::<Vec<((i64, i64), char)>>();
+
+            stack
+                .iter()
+                .enumerate()
+                .flat_map(move |(height, item)| {
+                    let y = -(height as i64) - 1;
+                    [((x, y), '['), ((x + 1, y), *item), ((x + 2, y), ']')]
+                })
+                .chain(label)
+        })
+        .collect()
+}
+
+fn animate(input: &str, args: &AnimateArgs) {
+    if !args.enabled {
+        return;
+    }
+
+    let (mut state, instructions) = match parse_input(input) {
+        Ok(parsed) => parsed,
+        Err(_) => return,
+    };
+    let mut animator = Animator::new(args.delay);
+    animator.draw(cells(&state));
+
+    // Errors are already reported by part two
+    let _ = run(
+        &CrateMover9001,
+        &mut state,
+        &instructions,
+        Some(&mut |state: &State| animator.draw(cells(state))),
+    );
+}
+
+// Prints the stacks before and after every instruction of the CrateMover 9001
+fn dump(input: &str) {
+    let (mut state, instructions) = match parse_input(input) {
+        Ok(parsed) => parsed,
+        Err(_) => return,
+    };
+    println!("{}\n", render(&state));
+
+    let mut index = 0;
+    let _ = run(
+        &CrateMover9001,
+        &mut state,
+        &instructions,
+        Some(&mut |state: &State| {
+            let (amount, from, to) = instructions[index];
+            index += 1;
+
+            println!("move {} from {} to {}", amount, from, to);
+            println!("{}\n", render(state));
+        }),
+    );
 }
 
 fn main() {
     let input = &advent_of_code::read_file("inputs", 5);
     advent_of_code::solve!(1, part_one, input);
     advent_of_code::solve!(2, part_two, input);
+    animate(input, &AnimateArgs::from_env());
+
+    // `--capacity <n>` also runs a crane that lifts at most n crates at once, `--dump` prints
+    // every intermediate state
+    let mut args = pico_args::Arguments::from_env();
+    if args.contains("--dump") {
+        dump(input);
+    }
+
+    let capacity: Option<usize> = match args.opt_value_from_str("--capacity") {
+        Ok(capacity) => capacity,
+        Err(e) => {
+            eprintln!("Invalid capacity: {}", e);
+            return;
+        }
+    };
+
+    if let Some(capacity) = capacity {
+        if let Some(top) = solve_with(&LimitedCrane(capacity), input) {
+            println!("Lifting at most {} crates: {}", capacity, top);
+        }
+    }
 }
 
 #[cfg(test)]
@@ -114,4 +412,71 @@ mod tests {
         let input = advent_of_code::read_file("examples", 5);
         assert_eq!(part_two(&input), Some("MCD".to_owned()));
     }
+
+    #[test]
+    fn test_limited_crane() {
+        let input = advent_of_code::read_file("examples", 5);
+        assert_eq!(solve_with(&LimitedCrane(1), &input), part_one(&input));
+        assert_eq!(solve_with(&LimitedCrane(3), &input), part_two(&input));
+        // The second instruction lifts N and D together, then Z on its own
+        assert_eq!(solve_with(&LimitedCrane(2), &input), Some("MCZ".to_owned()));
+
+        let (mut state, instructions) = parse_input(&input).unwrap();
+        assert_eq!(
+            run(&LimitedCrane(0), &mut state, &instructions, None)
+                .unwrap_err()
+                .reason,
+            "the crane can't lift any crates"
+        );
+    }
+
+    #[test]
+    fn test_render_round_trip() {
+        let input = advent_of_code::read_file("examples", 5);
+        let (state, _) = parse_input(&input).unwrap();
+        let diagram = input.split("\n\n").next().unwrap();
+
+        assert_eq!(render(&state), diagram);
+        assert_eq!(
+            parse_diagram(&render(&state).lines().collect::<Vec<&str>>()),
+            Ok(state)
+        );
+    }
+
+    #[test]
+    fn test_parse_diagram() {
+        let diagram = [
+            "                                        [K]   ",
+            "[A]                                     [J] [L]",
+            " 1   2   3   4   5   6   7   8   9  10  11  12",
+        ];
+        let state = parse_diagram(&diagram).unwrap();
+
+        assert_eq!(state.len(), 12);
+        assert_eq!(state[0], vec!['A']);
+        assert_eq!(state[10], vec!['J', 'K']);
+        assert_eq!(state[11], vec!['L']);
+        assert_eq!(render(&state).lines().last(), Some(diagram[2]));
+        assert!(parse_diagram(&["[A]", " 1   3"]).is_err());
+        assert!(parse_diagram(&["        [A]", " 1   2"]).is_err());
+    }
+
+    #[test]
+    fn test_illegal_move() {
+        let (mut state, _) = parse_input(&advent_of_code::read_file("examples", 5)).unwrap();
+        let instructions = vec![(1, 2, 1), (4, 2, 3)];
+        let error = run(&CrateMover9000, &mut state, &instructions, None).unwrap_err();
+
+        assert_eq!(error.index, 2);
+        assert_eq!(
+            error.state,
+            vec![vec!['Z', 'N', 'D'], vec!['M', 'C'], vec!['P']]
+        );
+        assert_eq!(
+            run(&CrateMover9000, &mut state, &vec![(1, 4, 1)], None)
+                .unwrap_err()
+                .reason,
+            "there is no stack 4"
+        );
+    }
 }
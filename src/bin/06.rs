@@ -1,51 +1,154 @@
-use itertools::Itertools;
+use std::{
+    collections::VecDeque,
+    fmt::Write,
+    io::{self, BufReader, Bytes, Read},
+};
 
-pub fn parse_input(input: &str) -> Vec<char> {
-    input.chars().collect()
+/// Positions right after every window of `length` distinct bytes in a datastream, which is
+/// read as it goes. Keeps a count of every byte in the window, so each byte costs O(1).
+pub struct Markers<R> {
+    bytes: Bytes<BufReader<R>>,
+    length: usize,
+    window: VecDeque<u8>,
+    counts: [usize; 256],
+    // Number of byte values that appear more than once in the window
+    duplicates: usize,
+    position: usize,
 }
 
-pub fn part_one(input: &str) -> Option<u32> {
-    let parsed = parse_input(input);
+impl<R: Read> Markers<R> {
+    pub fn new(reader: R, length: usize) -> Self {
+        Self {
+            bytes: BufReader::new(reader).bytes(),
+            length,
+            window: VecDeque::with_capacity(length + 1),
+            counts: [0; 256],
+            duplicates: 0,
+            position: 0,
+        }
+    }
+}
+
+impl<R: Read> Iterator for Markers<R> {
+    type Item = io::Result<usize>;
 
-    for (i, _) in parsed.iter().enumerate() {
-        if i < 4 {
-            continue;
+    fn next(&mut self) -> Option<Self::Item> {
+        // An empty window can't mark anything
+        if self.length == 0 {
+            return None;
         }
 
-        let sequence = &parsed[(i - 4)..i];
-        let unique = sequence.iter().unique().collect::<Vec<&char>>().len();
+        loop {
+            let byte = match self.bytes.next()? {
+                Ok(byte) => byte,
+                Err(e) => return Some(Err(e)),
+            };
+            self.position += 1;
 
-        if unique == 4 {
-            return Some(i as u32);
+            self.counts[byte as usize] += 1;
+            if self.counts[byte as usize] == 2 {
+                self.duplicates += 1;
+            }
+            self.window.push_back(byte);
+
+            if self.window.len() > self.length {
+                let old = self.window.pop_front().unwrap();
+
+                self.counts[old as usize] -= 1;
+                if self.counts[old as usize] == 1 {
+                    self.duplicates -= 1;
+                }
+            }
+
+            if self.window.len() == self.length && self.duplicates == 0 {
+                return Some(Ok(self.position));
+            }
         }
     }
+}
 
-    None
+/// Position right after the first marker of `length` distinct bytes, if there is one.
+pub fn find_marker(reader: impl Read, length: usize) -> io::Result<Option<usize>> {
+    Markers::new(reader, length).next().transpose()
 }
 
-pub fn part_two(input: &str) -> Option<u32> {
-    let parsed = parse_input(input);
+pub fn find_all_markers(reader: impl Read, length: usize) -> io::Result<Vec<usize>> {
+    Markers::new(reader, length).collect()
+}
 
-    for (i, _) in parsed.iter().enumerate() {
-        if i < 14 {
-            continue;
-        }
+const PACKET_LENGTH: usize = 4;
+const MESSAGE_LENGTH: usize = 14;
 
-        let sequence = &parsed[(i - 14)..i];
-        let unique = sequence.iter().unique().collect::<Vec<&char>>().len();
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct LineMarkers {
+    // Line of the datastream in the input, starting at 1
+    pub line: usize,
+    pub packet: Option<usize>,
+    pub message: Option<usize>,
+}
 
-        if unique == 14 {
-            return Some(i as u32);
-        }
+/// Every line of the input is its own datastream.
+pub fn line_markers(input: &str) -> Vec<LineMarkers> {
+    // Reading from a slice can't fail
+    input
+        .lines()
+        .enumerate()
+        .map(|(index, line)| LineMarkers {
+            line: index + 1,
+            packet: find_marker(line.as_bytes(), PACKET_LENGTH).unwrap(),
+            message: find_marker(line.as_bytes(), MESSAGE_LENGTH).unwrap(),
+        })
+        .collect()
+}
+
+pub fn format_report(input: &str) -> String {
+    let mut table = String::new();
+    let position = |marker: Option<usize>| marker.map_or("-".to_string(), |m| m.to_string());
+
+    writeln!(
+        table,
+        "{:>5}  {:>7}  {:>7}  Datastream",
+        "Line", "Packet", "Message"
+    )
+    .unwrap();
+
+    for (markers, line) in line_markers(input).iter().zip(input.lines()) {
+        writeln!(
+            table,
+            "{:>5}  {:>7}  {:>7}  {}",
+            markers.line,
+            position(markers.packet),
+            position(markers.message),
+            line
+        )
+        .unwrap();
     }
 
-    None
+    table
+}
+
+// Puzzle inputs hold a single datastream, so the parts only look at the first line
+pub fn part_one(input: &str) -> Option<u32> {
+    let marker = line_markers(input).first()?.packet?;
+
+    Some(marker as u32)
+}
+
+pub fn part_two(input: &str) -> Option<u32> {
+    let marker = line_markers(input).first()?.message?;
+
+    Some(marker as u32)
 }
 
 fn main() {
     let input = &advent_of_code::read_file("inputs", 6);
     advent_of_code::solve!(1, part_one, input);
     advent_of_code::solve!(2, part_two, input);
+
+    // `--report` lists the markers of every line
+    if pico_args::Arguments::from_env().contains("--report") {
+        print!("{}", format_report(input));
+    }
 }
 
 #[cfg(test)]
@@ -61,6 +164,39 @@ mod tests {
     #[test]
     fn test_part_two() {
         let input = advent_of_code::read_file("examples", 6);
-        assert_eq!(part_two(&input), None);
+        assert_eq!(part_two(&input), Some(19));
+    }
+
+    #[test]
+    fn test_line_markers() {
+        let input = advent_of_code::read_file("examples", 6);
+        let markers = line_markers(&input)
+            .iter()
+            .map(|markers| (markers.packet.unwrap(), markers.message.unwrap()))
+            .collect::<Vec<(usize, usize)>>();
+
+        assert_eq!(markers, vec![(7, 19), (5, 23), (6, 23), (10, 29), (11, 26)]);
+    }
+
+    #[test]
+    fn test_all_markers() {
+        assert_eq!(
+            find_all_markers("aabcbcd".as_bytes(), 3).unwrap(),
+            vec![4, 7]
+        );
+        assert_eq!(
+            find_all_markers("abc".as_bytes(), 1).unwrap(),
+            vec![1, 2, 3]
+        );
+    }
+
+    #[test]
+    fn test_edge_cases() {
+        // The marker can end on the last byte
+        assert_eq!(find_marker("aabcd".as_bytes(), 4).unwrap(), Some(5));
+        assert_eq!(find_marker("abc".as_bytes(), 4).unwrap(), None);
+        assert_eq!(find_marker("".as_bytes(), 4).unwrap(), None);
+        assert_eq!(find_marker("abc".as_bytes(), 0).unwrap(), None);
+        assert_eq!(find_marker("aaaa".as_bytes(), 2).unwrap(), None);
     }
 }
@@ -1,22 +1,51 @@
-use std::{cell::RefCell, ops::Index};
+use std::{
+    cmp::Ordering,
+    fmt::Write as _,
+    io::{self, BufRead, Write},
+};
 
+use advent_of_code::helpers::tree::{NodeId, Tree};
 use enum_as_inner::EnumAsInner;
-use itertools::Itertools;
+use serde::Serialize;
 
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, EnumAsInner)]
 pub enum Entry {
-    File(u32, String),
-    Directory(Vec<Entry>, String),
+    File(u64, String),
+    Directory(String),
+    // A line of `ls` output that couldn't be understood
+    Invalid(String),
+}
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, EnumAsInner)]
+pub enum Inode {
+    File(u64),
+    Directory,
 }
 
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, EnumAsInner)]
 pub enum Command {
     List(Vec<Entry>),
     Change(String),
+    // Any other command, or output that doesn't follow a command
+    Unknown(Vec<String>),
+}
+
+fn parse_entry(entry: &str) -> Entry {
+    match entry.split_whitespace().collect::<Vec<&str>>()[..] {
+        ["dir", name] => Entry::Directory(name.to_string()),
+        [size, name] => match size.parse() {
+            Ok(size) => Entry::File(size, name.to_string()),
+            Err(_) => Entry::Invalid(entry.to_string()),
+        },
+        _ => Entry::Invalid(entry.to_string()),
+    }
 }
 
 pub fn parse_input(input: &str) -> Vec<Command> {
-    let lines = input.lines().collect::<Vec<&str>>();
+    let lines = input
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .collect::<Vec<&str>>();
     let grouped = lines
         .iter()
         .enumerate()
@@ -26,9 +55,7 @@ pub fn parse_input(input: &str) -> Vec<Command> {
                 return true;
             }
 
-            let next_is_command = lines[index + 1].starts_with("$");
-
-            next_is_command
+            lines[index + 1].starts_with('$')
         })
         .map(|group| {
             group
@@ -39,163 +66,517 @@ pub fn parse_input(input: &str) -> Vec<Command> {
         .collect::<Vec<Vec<String>>>();
 
     grouped
-        .iter()
-        .map(|command| match command[0].as_str() {
-            "$ ls" => {
-                let entries = command[1..]
-                    .iter()
-                    .map(|entry| {
-                        if entry.starts_with("dir") {
-                            return Entry::Directory(
-                                Vec::new(),
-                                entry.strip_prefix("dir ").unwrap().to_string(),
-                            );
-                        }
-
-                        let file_entry = entry.split(" ").collect::<Vec<&str>>();
-
-                        Entry::File(file_entry[0].parse().unwrap(), file_entry[1].to_string())
-                    })
-                    .collect::<Vec<Entry>>();
-
-                Command::List(entries)
-            }
-            _ if command[0].starts_with("$ cd") => {
-                Command::Change(command[0].strip_prefix("$ cd ").unwrap().to_string())
+        .into_iter()
+        .flat_map(|command| {
+            let words = command[0].split_whitespace().collect::<Vec<&str>>();
+            let dir = match words[..] {
+                ["$", "ls"] => {
+                    return vec![Command::List(
+                        command[1..]
+                            .iter()
+                            .map(|entry| parse_entry(entry))
+                            .collect(),
+                    )]
+                }
+                ["$", "cd"] => "/",
+                ["$", "cd", dir] => dir,
+                _ => return vec![Command::Unknown(command)],
+            };
+
+            // `cd` doesn't print anything, stray output after it is reported on its own
+            let mut commands = vec![Command::Change(dir.to_string())];
+            if command.len() > 1 {
+                commands.push(Command::Unknown(command[1..].to_vec()));
             }
-            _ => todo!(),
+
+            commands
         })
         .collect::<Vec<Command>>()
 }
 
-fn get_nested_entry<'a>(entry: &'a mut Entry, stack: &'a [String]) -> &'a mut Entry {
-    if stack.len() == 0 {
-        return entry;
-    }
+// Follows `path` from `cwd`, creating directories that weren't listed yet. Returns `None` if the
+// path leads through a file or starts from a directory that is unknown itself.
+fn change_directory(
+    fs: &mut Tree<Inode>,
+    cwd: Option<NodeId>,
+    path: &str,
+    warnings: &mut Vec<String>,
+) -> Option<NodeId> {
+    let start = if path.starts_with('/') {
+        fs.root()
+    } else {
+        match cwd {
+            Some(cwd) => cwd,
+            None => {
+                warnings.push(format!("cd {}: the current directory is unknown", path));
+                return None;
+            }
+        }
+    };
 
-    let (entries, _) = entry.as_directory_mut().unwrap();
+    path.split('/')
+        .filter(|part| !part.is_empty() && *part != ".")
+        .try_fold(start, |id, part| {
+            if part == ".." {
+                return Some(fs.parent(id).unwrap_or(fs.root()));
+            }
 
-    for entry in entries {
-        match entry {
-            Entry::Directory(_, name) if name == stack.first().unwrap() => {
-                let rest = &stack[1..];
-                return get_nested_entry(entry, rest);
+            match fs.child(id, part) {
+                Some(child) if fs.get(child).value.is_directory() => Some(child),
+                Some(child) => {
+                    warnings.push(format!("cd {}: {} is a file", path, full_path(fs, child)));
+                    None
+                }
+                None => Some(fs.add_child(id, part, Inode::Directory)),
             }
-            _ => {}
+        })
+}
+
+// Adds a listed entry to `cwd`, listing the same entry again doesn't change anything
+fn add_entry(
+    fs: &mut Tree<Inode>,
+    cwd: NodeId,
+    name: &str,
+    inode: Inode,
+    warnings: &mut Vec<String>,
+) {
+    let existing = match fs.child(cwd, name) {
+        Some(existing) => existing,
+        None => {
+            fs.add_child(cwd, name, inode);
+            return;
+        }
+    };
+
+    let path = full_path(fs, existing);
+    match (fs.get(existing).value, inode) {
+        (Inode::File(old), Inode::File(new)) if old != new => {
+            warnings.push(format!(
+                "{} listed with size {}, previously {}",
+                path, new, old
+            ));
+            fs.get_mut(existing).value = inode;
+        }
+        (Inode::File(_), Inode::Directory) => {
+            warnings.push(format!("{} listed as a directory, previously a file", path));
+        }
+        (Inode::Directory, Inode::File(_)) => {
+            warnings.push(format!("{} listed as a file, previously a directory", path));
         }
+        _ => {}
     }
-
-    unreachable!()
 }
 
-fn build_filesystem(commands: &Vec<Command>) -> Entry {
-    let mut fs = Entry::Directory(Vec::new(), "/".to_string());
-    let mut stack: Vec<String> = vec![];
+/// Replays the transcript. Anything that doesn't add up is skipped and described in the
+/// returned warnings. Listings after a failed `cd` are skipped until a `cd` succeeds again.
+fn build_filesystem(commands: &[Command]) -> (Tree<Inode>, Vec<String>) {
+    let mut fs = Tree::new("/", Inode::Directory);
+    let mut warnings = vec![];
+    let mut cwd = Some(fs.root());
 
     for command in commands {
         match &command {
             Command::Change(dir) => {
-                if dir == ".." {
-                    stack.pop();
-                } else if dir == "/" {
-                    stack = vec![];
-                } else {
-                    stack.push(dir.clone());
-                }
+                cwd = change_directory(&mut fs, cwd, dir, &mut warnings);
             }
             Command::List(entries) => {
-                let (current_entries, _) = get_nested_entry(&mut fs, stack.as_slice())
-                    .as_directory_mut()
-                    .unwrap();
+                let cwd = match cwd {
+                    Some(cwd) => cwd,
+                    None => {
+                        warnings.push("ignoring ls output of an unknown directory".to_string());
+                        continue;
+                    }
+                };
 
                 for entry in entries {
                     match entry {
                         Entry::File(size, name) => {
-                            current_entries.push(Entry::File(*size, name.clone()));
+                            add_entry(&mut fs, cwd, name, Inode::File(*size), &mut warnings);
+                        }
+                        Entry::Directory(name) => {
+                            add_entry(&mut fs, cwd, name, Inode::Directory, &mut warnings);
                         }
-                        Entry::Directory(_, name) => {
-                            current_entries.push(Entry::Directory(vec![], name.clone()));
+                        Entry::Invalid(line) => {
+                            warnings.push(format!("ignoring invalid ls output {:?}", line));
                         }
                     }
                 }
             }
+            Command::Unknown(lines) => {
+                warnings.push(format!("ignoring unknown command {:?}", lines[0]));
+            }
         }
     }
 
-    fs
+    (fs, warnings)
 }
 
-fn total_under(node: &Entry, under: u32, results: &mut Vec<u32>) -> u32 {
-    let (entries, _) = node.as_directory().unwrap();
-    let mut local_size = 0;
+const DISK_SIZE: u64 = 70000000;
+const NEEDED_SPACE: u64 = 30000000;
 
-    for entry in entries {
-        match entry {
-            Entry::File(size, _) => {
-                local_size += size;
-            }
-            Entry::Directory(_, _) => {
-                local_size += total_under(entry, under, results);
-            }
+// Every directory and its size, the root always comes first
+fn directory_sizes(fs: &Tree<Inode>) -> Vec<(NodeId, u64)> {
+    let totals = fs.subtree_sums(|inode| match inode {
+        Inode::File(size) => *size,
+        Inode::Directory => 0,
+    });
+
+    fs.iter()
+        .filter(|(_, node)| node.value.is_directory())
+        .map(|(id, _)| (id, totals[id]))
+        .collect()
+}
+
+/// The smallest directory that frees up enough space for the update when deleted.
+pub fn deletion_candidate(fs: &Tree<Inode>) -> Option<(NodeId, u64)> {
+    let sizes = directory_sizes(fs);
+    let root_size = sizes[0].1;
+
+    let current_free = DISK_SIZE.saturating_sub(root_size);
+    let need_to_free = NEEDED_SPACE.saturating_sub(current_free);
+
+    sizes
+        .into_iter()
+        .filter(|&(_, size)| size >= need_to_free)
+        .min_by_key(|&(_, size)| size)
+}
+
+pub fn part_one(input: &str) -> Option<u64> {
+    let commands = parse_input(input);
+    let (filesystem, _) = build_filesystem(&commands);
+
+    Some(
+        directory_sizes(&filesystem)
+            .iter()
+            .map(|&(_, size)| size)
+            .filter(|&size| size < 100000)
+            .sum::<u64>(),
+    )
+}
+
+pub fn part_two(input: &str) -> Option<u64> {
+    let commands = parse_input(input);
+    let (filesystem, _) = build_filesystem(&commands);
+
+    deletion_candidate(&filesystem).map(|(_, size)| size)
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum JsonNode {
+    Directory {
+        name: String,
+        size: u64,
+        children: Vec<JsonNode>,
+    },
+    File {
+        name: String,
+        size: u64,
+    },
+}
+
+/// Nested representation of the subtree at `id`, directories include their total size.
+pub fn to_json(fs: &Tree<Inode>, id: NodeId) -> JsonNode {
+    fn build(fs: &Tree<Inode>, sizes: &[u64], id: NodeId) -> JsonNode {
+        let node = fs.get(id);
+
+        match node.value {
+            Inode::File(size) => JsonNode::File {
+                name: node.name.clone(),
+                size,
+            },
+            Inode::Directory => JsonNode::Directory {
+                name: node.name.clone(),
+                size: sizes[id],
+                children: node
+                    .children
+                    .iter()
+                    .map(|&child| build(fs, sizes, child))
+                    .collect(),
+            },
         }
     }
 
-    if local_size < under {
-        results.push(local_size)
+    let sizes = fs.subtree_sums(|inode| *inode.as_file().unwrap_or(&0));
+    build(fs, &sizes, id)
+}
+
+/// Every directory with its full path like `du`, largest first.
+pub fn format_du(fs: &Tree<Inode>) -> String {
+    let mut sizes = directory_sizes(fs)
+        .into_iter()
+        .map(|(id, size)| (size, full_path(fs, id)))
+        .collect::<Vec<(u64, String)>>();
+    sizes.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(&b.1)));
+
+    let mut output = sizes
+        .iter()
+        .map(|(size, path)| format!("{}\t{}\n", size, path))
+        .collect::<String>();
+
+    if let Some((id, size)) = deletion_candidate(fs) {
+        writeln!(
+            output,
+            "\nDelete {} to free up {} for the update",
+            full_path(fs, id),
+            size
+        )
+        .unwrap();
     }
 
-    local_size
+    output
+}
+
+/// Answers shell commands about a reconstructed filesystem: `cd`, `ls`, `pwd`, `du -s`,
+/// `find` and `tree`. Paths may be absolute or relative to the working directory.
+pub struct Shell<'a> {
+    fs: &'a Tree<Inode>,
+    // Total size of every node, indexed by `NodeId`
+    sizes: Vec<u64>,
+    cwd: NodeId,
 }
 
-fn total(node: &Entry, results: &mut Vec<u32>) -> u32 {
-    let (entries, _) = node.as_directory().unwrap();
-    let mut local_size = 0;
+impl<'a> Shell<'a> {
+    pub fn new(fs: &'a Tree<Inode>) -> Self {
+        Self {
+            fs,
+            sizes: fs.subtree_sums(|inode| match inode {
+                Inode::File(size) => *size,
+                Inode::Directory => 0,
+            }),
+            cwd: fs.root(),
+        }
+    }
+
+    pub fn pwd(&self) -> String {
+        full_path(self.fs, self.cwd)
+    }
 
-    for entry in entries {
-        match entry {
-            Entry::File(size, _) => {
-                local_size += size;
+    pub fn resolve(&self, path: &str) -> Result<NodeId, String> {
+        let start = if path.starts_with('/') {
+            self.fs.root()
+        } else {
+            self.cwd
+        };
+
+        path.split('/')
+            .filter(|part| !part.is_empty() && *part != ".")
+            .try_fold(start, |id, part| match part {
+                ".." => Ok(self.fs.parent(id).unwrap_or(self.fs.root())),
+                _ => self
+                    .fs
+                    .child(id, part)
+                    .ok_or_else(|| format!("{}: no such file or directory", path)),
+            })
+    }
+
+    /// Runs a single command line and returns its output.
+    pub fn run(&mut self, line: &str) -> Result<String, String> {
+        let words = line.split_whitespace().collect::<Vec<&str>>();
+        let (&command, args) = match words.split_first() {
+            Some(split) => split,
+            None => return Ok(String::new()),
+        };
+
+        match command {
+            "cd" => {
+                let id = self.resolve(args.first().copied().unwrap_or("/"))?;
+                if !self.fs.get(id).value.is_directory() {
+                    return Err(format!("cd: {}: not a directory", args[0]));
+                }
+
+                self.cwd = id;
+                Ok(String::new())
             }
-            Entry::Directory(_, _) => {
-                local_size += total(entry, results);
+            "pwd" => Ok(format!("{}\n", self.pwd())),
+            "ls" => {
+                let id = self.resolve(args.first().copied().unwrap_or("."))?;
+
+                Ok(self
+                    .listing(id)
+                    .into_iter()
+                    .map(|line| line + "\n")
+                    .collect())
             }
+            "du" => match args {
+                ["-s"] | ["-s", _] => {
+                    let path = args.get(1).copied().unwrap_or(".");
+                    let id = self.resolve(path)?;
+
+                    Ok(format!("{}\t{}\n", self.sizes[id], full_path(self.fs, id)))
+                }
+                _ => Err("usage: du -s [path]".to_string()),
+            },
+            "find" => self.find(args),
+            "tree" => {
+                let id = self.resolve(args.first().copied().unwrap_or("."))?;
+                let mut output = String::new();
+                self.write_tree(&mut output, id, 0);
+
+                Ok(output)
+            }
+            "help" => Ok("commands: cd, ls, pwd, du -s, find, tree, exit\n".to_string()),
+            _ => Err(format!("{}: command not found", command)),
         }
     }
 
-    results.push(local_size);
+    // Entries of `id` in the format of the transcript
+    fn listing(&self, id: NodeId) -> Vec<String> {
+        let node = self.fs.get(id);
+        let entries = if node.value.is_directory() {
+            node.children.clone()
+        } else {
+            vec![id]
+        };
+
+        entries
+            .iter()
+            .map(|&child| {
+                let child = self.fs.get(child);
+
+                match child.value {
+                    Inode::File(size) => format!("{} {}", size, child.name),
+                    Inode::Directory => format!("dir {}", child.name),
+                }
+            })
+            .collect()
+    }
 
-    local_size
-}
+    // `find [path] [-type d|f] [-size +N|-N|N]`, sizes of directories include their contents
+    fn find(&self, args: &[&str]) -> Result<String, String> {
+        let usage = || "usage: find [path] [-type d|f] [-size +N|-N|N]".to_string();
+        let (path, mut options) = match args.first() {
+            Some(path) if !path.starts_with('-') => (*path, &args[1..]),
+            _ => (".", args),
+        };
+
+        let mut kind: Option<bool> = None;
+        let mut size: Option<(Ordering, u64)> = None;
+
+        while let [option, value, rest @ ..] = options {
+            match *option {
+                "-type" => match *value {
+                    "d" => kind = Some(true),
+                    "f" => kind = Some(false),
+                    _ => return Err(usage()),
+                },
+                "-size" => {
+                    let (ordering, number) = if let Some(number) = value.strip_prefix('+') {
+                        (Ordering::Greater, number)
+                    } else if let Some(number) = value.strip_prefix('-') {
+                        (Ordering::Less, number)
+                    } else {
+                        (Ordering::Equal, *value)
+                    };
+
+                    size = Some((ordering, number.parse().map_err(|_| usage())?));
+                }
+                _ => return Err(usage()),
+            }
 
-pub fn part_one(input: &str) -> Option<u32> {
-    let commands = parse_input(input);
-    let filesystem = build_filesystem(&commands);
+            options = rest;
+        }
+
+        if !options.is_empty() {
+            return Err(usage());
+        }
+
+        let start = self.resolve(path)?;
+        let mut output = String::new();
+        let mut stack = vec![start];
 
-    let mut results = vec![];
-    total_under(&filesystem, 100000, &mut results);
+        while let Some(id) = stack.pop() {
+            let node = self.fs.get(id);
+            let matches_kind = kind.is_none_or(|directory| node.value.is_directory() == directory);
+            let matches_size =
+                size.is_none_or(|(ordering, size)| self.sizes[id].cmp(&size) == ordering);
 
-    Some(results.iter().sum::<u32>())
+            if matches_kind && matches_size {
+                writeln!(output, "{}", full_path(self.fs, id)).unwrap();
+            }
+
+            stack.extend(node.children.iter().rev());
+        }
+
+        Ok(output)
+    }
+
+    // Same format as the example in the puzzle
+    fn write_tree(&self, output: &mut String, id: NodeId, depth: usize) {
+        let node = self.fs.get(id);
+        let description = match node.value {
+            Inode::File(size) => format!("file, size={}", size),
+            Inode::Directory => "dir".to_string(),
+        };
+
+        writeln!(
+            output,
+            "{}- {} ({})",
+            "  ".repeat(depth),
+            node.name,
+            description
+        )
+        .unwrap();
+
+        for &child in &node.children {
+            self.write_tree(output, child, depth + 1);
+        }
+    }
 }
 
-pub fn part_two(input: &str) -> Option<u32> {
-    let commands = parse_input(input);
-    let filesystem = build_filesystem(&commands);
+pub fn full_path(fs: &Tree<Inode>, id: NodeId) -> String {
+    let path = fs.path(id);
 
-    let mut results = vec![];
-    let root_size = total(&filesystem, &mut results);
+    format!("/{}", path[1..].join("/"))
+}
 
-    let current_free = 70000000 - root_size;
-    let need_to_free = 30000000 - current_free;
+// `--shell` reads commands from stdin, interactively or from a script
+fn shell(filesystem: &Tree<Inode>) {
+    let mut shell = Shell::new(filesystem);
 
-    results.sort();
+    loop {
+        print!("{} $ ", shell.pwd());
+        io::stdout().flush().unwrap();
 
-    Some(*results.iter().find(|&&size| size >= need_to_free).unwrap())
+        let mut line = String::new();
+        if io::stdin().lock().read_line(&mut line).unwrap_or(0) == 0 || line.trim() == "exit" {
+            println!();
+            break;
+        }
+
+        match shell.run(&line) {
+            Ok(output) => print!("{}", output),
+            Err(e) => eprintln!("{}", e),
+        }
+    }
 }
 
 fn main() {
     let input = &advent_of_code::read_file("inputs", 7);
     advent_of_code::solve!(1, part_one, input);
     advent_of_code::solve!(2, part_two, input);
+
+    let (filesystem, warnings) = build_filesystem(&parse_input(input));
+    for warning in warnings {
+        eprintln!("Warning: {}", warning);
+    }
+
+    // `--json` exports the filesystem, `--du` lists all directories by size and `--shell`
+    // explores it
+    let mut args = pico_args::Arguments::from_env();
+
+    if args.contains("--json") {
+        let json = to_json(&filesystem, filesystem.root());
+        println!("{}", serde_json::to_string_pretty(&json).unwrap());
+    }
+
+    if args.contains("--du") {
+        print!("{}", format_du(&filesystem));
+    }
+
+    if args.contains("--shell") {
+        shell(&filesystem);
+    }
 }
 
 #[cfg(test)]
@@ -213,4 +594,183 @@ mod tests {
         let input = advent_of_code::read_file("examples", 7);
         assert_eq!(part_two(&input), Some(24933642));
     }
+
+    #[test]
+    fn test_large_sizes() {
+        let input = "$ cd /\n$ ls\ndir a\n3000000000 x\n$ cd a\n$ ls\n3000000000 y\n50 z";
+        let (filesystem, _) = build_filesystem(&parse_input(input));
+
+        assert_eq!(part_two(input), Some(3000000050));
+        assert!(format_du(&filesystem).starts_with("6000000050\t/\n3000000050\t/a\n"));
+    }
+
+    #[test]
+    fn test_du() {
+        let input = advent_of_code::read_file("examples", 7);
+        let (filesystem, _) = build_filesystem(&parse_input(&input));
+
+        assert_eq!(
+            format_du(&filesystem),
+            [
+                "48381165\t/",
+                "24933642\t/d",
+                "94853\t/a",
+                "584\t/a/e",
+                "",
+                "Delete /d to free up 24933642 for the update",
+                "",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn test_json() {
+        let (filesystem, _) = reconstruct(&["$ ls", "dir a", "$ cd a", "$ ls", "5 b"]);
+        let json = serde_json::to_value(to_json(&filesystem, filesystem.root())).unwrap();
+
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "type": "directory",
+                "name": "/",
+                "size": 5,
+                "children": [{
+                    "type": "directory",
+                    "name": "a",
+                    "size": 5,
+                    "children": [{ "type": "file", "name": "b", "size": 5 }]
+                }]
+            })
+        );
+    }
+
+    #[test]
+    fn test_shell() {
+        let input = advent_of_code::read_file("examples", 7);
+        let (filesystem, _) = build_filesystem(&parse_input(&input));
+        let mut shell = Shell::new(&filesystem);
+
+        assert_eq!(shell.run("cd /a/e"), Ok(String::new()));
+        assert_eq!(shell.run("pwd"), Ok("/a/e\n".to_string()));
+        assert_eq!(shell.run("ls"), Ok("584 i\n".to_string()));
+        assert_eq!(shell.run("du -s ../.."), Ok("48381165\t/\n".to_string()));
+        assert_eq!(
+            shell.run("cd ../../d/j").unwrap_err(),
+            "cd: ../../d/j: not a directory"
+        );
+        assert_eq!(
+            shell.run("cd /x").unwrap_err(),
+            "/x: no such file or directory"
+        );
+        assert_eq!(
+            shell.run("find / -type d -size -100000"),
+            Ok("/a\n/a/e\n".to_string())
+        );
+        assert_eq!(
+            shell.run("find / -type f -size +100000"),
+            Ok("/b.txt\n/c.dat\n/d/j\n/d/d.log\n/d/d.ext\n/d/k\n".to_string())
+        );
+        assert!(shell.run("find -size é5").is_err());
+        assert!(shell.run("find -type x").is_err());
+        assert_eq!(
+            shell.run("tree /a"),
+            Ok([
+                "- a (dir)",
+                "  - e (dir)",
+                "    - i (file, size=584)",
+                "  - f (file, size=29116)",
+                "  - g (file, size=2557)",
+                "  - h.lst (file, size=62596)",
+                "",
+            ]
+            .join("\n"))
+        );
+    }
+
+    fn reconstruct(transcript: &[&str]) -> (Tree<Inode>, Vec<String>) {
+        build_filesystem(&parse_input(&transcript.join("\n")))
+    }
+
+    fn size_of(fs: &Tree<Inode>, path: &str) -> u64 {
+        let id = Shell::new(fs).resolve(path).unwrap();
+        fs.subtree_sums(|inode| *inode.as_file().unwrap_or(&0))[id]
+    }
+
+    #[test]
+    fn test_unknown_lines() {
+        let (fs, warnings) = reconstruct(&["1 stray", "$ ls", "10 a", "oops", "$ rm a", "$ cd"]);
+
+        assert_eq!(size_of(&fs, "/"), 10);
+        assert_eq!(
+            warnings,
+            vec![
+                "ignoring unknown command \"1 stray\"",
+                "ignoring invalid ls output \"oops\"",
+                "ignoring unknown command \"$ rm a\"",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_output_after_cd() {
+        let (fs, warnings) = reconstruct(&["$ cd a", "stray", "$ ls", "5 b"]);
+
+        assert_eq!(size_of(&fs, "/a"), 5);
+        assert_eq!(warnings, vec!["ignoring unknown command \"stray\""]);
+    }
+
+    #[test]
+    fn test_implicit_directories() {
+        let (fs, warnings) = reconstruct(&[
+            "$ cd a",
+            "$ cd b",
+            "$ ls",
+            "5 c",
+            "$ cd /x/y",
+            "$ ls",
+            "7 z",
+        ]);
+
+        assert_eq!(size_of(&fs, "/a"), 5);
+        assert_eq!(size_of(&fs, "/x/y"), 7);
+        assert_eq!(size_of(&fs, "/"), 12);
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn test_relisting() {
+        let (fs, warnings) = reconstruct(&[
+            "$ ls", "dir a", "10 b", "$ cd a", "$ ls", "5 c", "$ cd /", "$ ls", "dir a", "10 b",
+            "$ cd /a", "$ ls", "5 c",
+        ]);
+
+        assert_eq!(fs.len(), 4);
+        assert_eq!(size_of(&fs, "/"), 15);
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn test_inconsistent_listing() {
+        let (fs, warnings) = reconstruct(&[
+            "$ ls", "10 b", "dir c", "$ ls", "20 b", "5 c", "$ cd b", "$ ls", "1 d", "$ cd ..",
+            "$ ls", "3 e", "$ cd /c", "$ ls", "4 f",
+        ]);
+
+        // Nothing is listed while the current directory is unknown
+        assert_eq!(size_of(&fs, "/c"), 4);
+        assert_eq!(size_of(&fs, "/"), 24);
+        assert_eq!(fs.len(), 4);
+        assert_eq!(
+            warnings,
+            vec![
+                "/b listed with size 20, previously 10",
+                "/c listed as a file, previously a directory",
+                "cd b: /b is a file",
+                "ignoring ls output of an unknown directory",
+                "cd ..: the current directory is unknown",
+                "ignoring ls output of an unknown directory",
+            ]
+        );
+    }
 }
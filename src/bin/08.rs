@@ -1,15 +1,43 @@
-use itertools::Itertools;
-
-pub fn parse_input(input: &str) -> Vec<Vec<u32>> {
-    input
+use std::fmt::Write;
+
+use advent_of_code::{
+    helpers::visualize::{
+        write_pgm, write_png, Frame, Palette, RenderArgs, BLACK, GOLD, GREEN, RED,
+    },
+    ANSI_BOLD, ANSI_RESET,
+};
+
+/// Parses the forest, which has to be a rectangle of digits.
+pub fn parse_input(input: &str) -> Result<Vec<Vec<u32>>, String> {
+    let map = input
         .lines()
-        .map(|line| line.chars().map(|c| c.to_digit(10).unwrap()).collect())
-        .collect()
+        .enumerate()
+        .map(|(index, line)| {
+            line.chars()
+                .map(|c| {
+                    c.to_digit(10)
+                        .ok_or_else(|| format!("line {}: invalid height {:?}", index + 1, c))
+                })
+                .collect::<Result<Vec<u32>, String>>()
+        })
+        .collect::<Result<Vec<Vec<u32>>, String>>()?;
+
+    let width = map.first().map_or(0, |row| row.len());
+    if let Some(index) = map.iter().position(|row| row.len() != width) {
+        return Err(format!(
+            "line {}: expected {} trees, found {}",
+            index + 1,
+            width,
+            map[index].len()
+        ));
+    }
+
+    Ok(map)
 }
 
-pub fn get_all(map: &Vec<Vec<u32>>) -> Vec<(usize, usize)> {
+pub fn get_all(map: &[Vec<u32>]) -> Vec<(usize, usize)> {
     let height = map.len();
-    let width = map[0].len();
+    let width = map.first().map_or(0, |row| row.len());
 
     (0..height)
         .flat_map(|height| {
@@ -20,7 +48,7 @@ pub fn get_all(map: &Vec<Vec<u32>>) -> Vec<(usize, usize)> {
         .collect()
 }
 
-// pub fn get_adjacent_visible(map: &Vec<Vec<u32>>, pos: &(usize, usize)) -> Vec<(usize, usize)> {
+// pub fn get_adjacent_visible(map: &[Vec<u32>], pos: &(usize, usize)) -> Vec<(usize, usize)> {
 //     let mut to_check = vec![];
 //     let (height, width) = *pos;
 
@@ -47,7 +75,9 @@ pub fn get_all(map: &Vec<Vec<u32>>) -> Vec<(usize, usize)> {
 //         .collect()
 // }
 
-pub fn visible_from_edge(map: &Vec<Vec<u32>>, pos: &(usize, usize)) -> bool {
+// Reference implementations that walk every ray, O(n³) but easy to check against the puzzle
+
+pub fn visible_from_edge(map: &[Vec<u32>], pos: &(usize, usize)) -> bool {
     let (height, width) = *pos;
 
     if height == 0 || width == 0 || height == (map.len() - 1) || width == (map[0].len() - 1) {
@@ -97,18 +127,7 @@ pub fn visible_from_edge(map: &Vec<Vec<u32>>, pos: &(usize, usize)) -> bool {
     false
 }
 
-pub fn part_one(input: &str) -> Option<u32> {
-    let map = parse_input(input);
-    let all = get_all(&map);
-    let points = all
-        .iter()
-        .filter(|point| visible_from_edge(&map, point))
-        .collect::<Vec<&(usize, usize)>>();
-
-    Some(points.len() as u32)
-}
-
-pub fn score(map: &Vec<Vec<u32>>, pos: &(usize, usize)) -> u32 {
+pub fn score(map: &[Vec<u32>], pos: &(usize, usize)) -> u32 {
     let mut scores: Vec<usize> = vec![];
     let (height, width) = *pos;
 
@@ -155,12 +174,12 @@ pub fn score(map: &Vec<Vec<u32>>, pos: &(usize, usize)) -> u32 {
             break;
         }
 
-        if w == (map.len() - 1) {
+        if w == (map[0].len() - 1) {
             scores.push(w - width);
         }
     }
 
-    if scores.len() == 0 {
+    if scores.is_empty() {
         return 1;
     }
 
@@ -171,23 +190,246 @@ pub fn score(map: &Vec<Vec<u32>>, pos: &(usize, usize)) -> u32 {
         .unwrap()
 }
 
-pub fn part_two(input: &str) -> Option<u32> {
-    let map = parse_input(input);
-    let all = get_all(&map);
-    let mut points = all
+// Every row and column of the map in both directions, as the positions along it
+fn lines(height: usize, width: usize) -> impl Iterator<Item = Vec<(usize, usize)>> {
+    let rows = (0..height).map(move |h| (0..width).map(|w| (h, w)).collect::<Vec<_>>());
+    let columns = (0..width).map(move |w| (0..height).map(|h| (h, w)).collect::<Vec<_>>());
+
+    rows.chain(columns).flat_map(|line| {
+        let reversed = line.iter().rev().copied().collect();
+        [line, reversed]
+    })
+}
+
+/// Whether every tree can be seen from outside the forest, in O(n²) by sweeping every row and
+/// column from both sides while keeping track of the tallest tree so far.
+pub fn visibility(map: &[Vec<u32>]) -> Vec<Vec<bool>> {
+    let width = map.first().map_or(0, |row| row.len());
+    let mut visible = vec![vec![false; width]; map.len()];
+
+    for line in lines(map.len(), width) {
+        let mut tallest: Option<u32> = None;
+
+        for (h, w) in line {
+            if tallest.is_none_or(|tallest| map[h][w] > tallest) {
+                visible[h][w] = true;
+                tallest = Some(map[h][w]);
+            }
+        }
+    }
+
+    visible
+}
+
+/// Scenic score of every tree, in O(n²). Each sweep keeps a stack of the trees that could still
+/// block the view, shortest on top, so every tree is pushed and popped at most once per sweep.
+pub fn scenic_scores(map: &[Vec<u32>]) -> Vec<Vec<u64>> {
+    let width = map.first().map_or(0, |row| row.len());
+    let mut scores = vec![vec![1; width]; map.len()];
+
+    for line in lines(map.len(), width) {
+        // Indices along the line of the trees that haven't been overshadowed yet
+        let mut stack: Vec<usize> = vec![];
+
+        for (i, &(h, w)) in line.iter().enumerate() {
+            while let Some(&top) = stack.last() {
+                let (top_h, top_w) = line[top];
+                if map[top_h][top_w] >= map[h][w] {
+                    break;
+                }
+
+                stack.pop();
+            }
+
+            // The view ends at the first tree at least as tall, or at the edge
+            let distance = stack.last().map_or(i, |&blocker| i - blocker);
+            scores[h][w] *= distance as u64;
+            stack.push(i);
+        }
+    }
+
+    scores
+}
+
+fn parse_or_report(input: &str) -> Option<Vec<Vec<u32>>> {
+    parse_input(input)
+        .map_err(|e| eprintln!("Invalid forest: {}", e))
+        .ok()
+}
+
+pub fn part_one(input: &str) -> Option<u32> {
+    let map = parse_or_report(input)?;
+    let visible = visibility(&map).iter().flatten().filter(|&&v| v).count();
+
+    Some(visible as u32)
+}
+
+pub fn part_two(input: &str) -> Option<u64> {
+    let map = parse_or_report(input)?;
+
+    scenic_scores(&map).iter().flatten().copied().max()
+}
+
+/// The tree with the highest scenic score, the first one in reading order on ties.
+pub fn best_tree(scores: &[Vec<u64>]) -> Option<((usize, usize), u64)> {
+    scores
         .iter()
-        .map(|point| score(&map, point))
-        .collect::<Vec<u32>>();
+        .enumerate()
+        .flat_map(|(h, row)| {
+            row.iter()
+                .enumerate()
+                .map(move |(w, &score)| ((h, w), score))
+        })
+        .rev()
+        .max_by_key(|&(_, score)| score)
+}
 
-    points.sort();
+// Scores are spread over `levels` shades on a log scale, they grow quickly towards the middle
+fn heat_level(score: u64, max: u64, levels: usize) -> usize {
+    if max == 0 {
+        return 0;
+    }
 
-    Some(*points.last().unwrap())
+    let level = (score as f64).ln_1p() / (max as f64).ln_1p() * (levels - 1) as f64;
+    level.round() as usize
+}
+
+const ANSI_BEST: &str = "\x1b[1;97;41m";
+const ANSI_HIDDEN: &str = "\x1b[90m";
+const ANSI_VISIBLE: &str = "\x1b[32m";
+// The lighter part of the grayscale ramp of the 256 color palette, so every height stays readable
+const ANSI_GRAY_START: usize = 238;
+const ANSI_GRAY_LEVELS: usize = 18;
+
+/// Heights of all trees, visible trees in green and hidden ones in gray.
+pub fn format_visibility(map: &[Vec<u32>], visible: &[Vec<bool>], best: (usize, usize)) -> String {
+    format_map(map, best, |h, w| {
+        if visible[h][w] {
+            format!("{}{}", ANSI_BOLD, ANSI_VISIBLE)
+        } else {
+            ANSI_HIDDEN.to_string()
+        }
+    })
+}
+
+/// Heights of all trees, brighter the higher their scenic score.
+pub fn format_scenic(map: &[Vec<u32>], scores: &[Vec<u64>], best: (usize, usize)) -> String {
+    let max = scores[best.0][best.1];
+
+    format_map(map, best, |h, w| {
+        let level = heat_level(scores[h][w], max, ANSI_GRAY_LEVELS);
+        format!("\x1b[38;5;{}m", ANSI_GRAY_START + level)
+    })
+}
+
+// Draws every height in the color picked by `style`, the best tree stands out in red
+fn format_map(
+    map: &[Vec<u32>],
+    best: (usize, usize),
+    style: impl Fn(usize, usize) -> String,
+) -> String {
+    let mut output = String::new();
+
+    for (h, row) in map.iter().enumerate() {
+        for (w, height) in row.iter().enumerate() {
+            let style = if (h, w) == best {
+                ANSI_BEST.to_string()
+            } else {
+                style(h, w)
+            };
+
+            write!(output, "{}{}{}", style, height, ANSI_RESET).unwrap();
+        }
+
+        writeln!(output).unwrap();
+    }
+
+    output
+}
+
+fn visibility_frame(visible: &[Vec<bool>], best: (usize, usize)) -> (Frame, Palette) {
+    let width = visible.first().map_or(0, |row| row.len());
+    let frame = Frame::from_fn(width, visible.len(), |w, h| match (h, w) {
+        position if position == best => 2,
+        _ => visible[h][w] as u8,
+    });
+
+    (frame, Palette::new(&[BLACK, GREEN, RED]))
+}
+
+// Palette index of the best tree in the scenic heatmap, after all the shades
+const BEST: u8 = 255;
+
+fn scenic_frame(scores: &[Vec<u64>], best: (usize, usize)) -> (Frame, Palette) {
+    let width = scores.first().map_or(0, |row| row.len());
+    let max = scores[best.0][best.1];
+    let frame = Frame::from_fn(width, scores.len(), |w, h| match (h, w) {
+        position if position == best => BEST,
+        _ => heat_level(scores[h][w], max, BEST as usize) as u8,
+    });
+
+    (
+        frame,
+        Palette::gradient(BLACK, GOLD, BEST as usize).with_colors(&[RED]),
+    )
+}
+
+// `--heatmap <visibility|scenic>` prints the map to the terminal, `--png` and `--pgm` draw it
+// to an image. Images default to the scenic scores.
+fn heatmap(input: &str) {
+    let mut args = pico_args::Arguments::from_env();
+    let kind: Option<String> = args.opt_value_from_str("--heatmap").unwrap_or(None);
+    let render = RenderArgs::from_env();
+
+    if kind.is_none() && !render.is_enabled() {
+        return;
+    }
+
+    let map = match parse_input(input) {
+        Ok(map) => map,
+        Err(_) => return,
+    };
+    let scores = scenic_scores(&map);
+    let best = match best_tree(&scores) {
+        Some((best, _)) => best,
+        None => return,
+    };
+
+    let visibility_map = kind.as_deref() == Some("visibility");
+    let (frame, palette) = if visibility_map {
+        let visible = visibility(&map);
+        if kind.is_some() {
+            print!("{}", format_visibility(&map, &visible, best));
+        }
+
+        visibility_frame(&visible, best)
+    } else {
+        if kind.is_some() {
+            print!("{}", format_scenic(&map, &scores, best));
+        }
+
+        scenic_frame(&scores, best)
+    };
+
+    if let Some(path) = &render.png {
+        if let Err(e) = write_png(path, &frame, &palette, 4) {
+            eprintln!("Failed to write {:?}: {}", path, e);
+            return;
+        }
+    }
+
+    if let Some(path) = &render.pgm {
+        if let Err(e) = write_pgm(path, &frame, &palette, 4) {
+            eprintln!("Failed to write {:?}: {}", path, e);
+        }
+    }
 }
 
 fn main() {
     let input = &advent_of_code::read_file("inputs", 8);
     advent_of_code::solve!(1, part_one, input);
     advent_of_code::solve!(2, part_two, input);
+    heatmap(input);
 }
 
 #[cfg(test)]
@@ -205,4 +447,84 @@ mod tests {
         let input = advent_of_code::read_file("examples", 8);
         assert_eq!(part_two(&input), Some(8));
     }
+
+    // Forests generated from a simple linear congruential generator
+    fn generate(height: usize, width: usize, seed: u64) -> Vec<Vec<u32>> {
+        let mut state = seed;
+
+        (0..height)
+            .map(|_| {
+                (0..width)
+                    .map(|_| {
+                        state = state
+                            .wrapping_mul(6364136223846793005)
+                            .wrapping_add(1442695040888963407);
+                        (state >> 33) as u32 % 10
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_best_tree() {
+        let input = advent_of_code::read_file("examples", 8);
+        let scores = scenic_scores(&parse_input(&input).unwrap());
+
+        assert_eq!(best_tree(&scores), Some(((3, 2), 8)));
+        assert_eq!(heat_level(8, 8, 24), 23);
+        assert_eq!(heat_level(0, 8, 24), 0);
+
+        let (frame, _) = scenic_frame(&scores, (3, 2));
+        assert_eq!(frame.get(2, 3), BEST);
+    }
+
+    #[test]
+    fn test_non_square() {
+        let wide = "1111111\n1232101\n1111111";
+        let tall = "111\n121\n131\n121\n111\n101\n111";
+
+        assert_eq!(part_one(wide), Some(19));
+        assert_eq!(part_two(wide), Some(8));
+        assert_eq!(part_one(tall), Some(19));
+        assert_eq!(part_two(tall), Some(8));
+    }
+
+    #[test]
+    fn test_invalid_forest() {
+        assert_eq!(
+            parse_input("123\n12\n123"),
+            Err("line 2: expected 3 trees, found 2".to_string())
+        );
+        assert_eq!(
+            parse_input("12x"),
+            Err("line 1: invalid height 'x'".to_string())
+        );
+        assert_eq!(part_one("1\n11"), None);
+        assert_eq!(part_two(""), None);
+    }
+
+    #[test]
+    fn test_matches_reference() {
+        for (seed, (height, width)) in [(12, 12), (5, 17), (17, 5), (3, 9)]
+            .into_iter()
+            .cycle()
+            .take(20)
+            .enumerate()
+        {
+            let map = generate(height, width, seed as u64);
+            let visible = visibility(&map);
+            let scores = scenic_scores(&map);
+
+            for position in get_all(&map) {
+                let (h, w) = position;
+                assert_eq!(visible[h][w], visible_from_edge(&map, &position));
+
+                // The reference scores edge trees as 1 instead of 0
+                if h > 0 && w > 0 && h < height - 1 && w < width - 1 {
+                    assert_eq!(scores[h][w], score(&map, &position) as u64);
+                }
+            }
+        }
+    }
 }
@@ -1,189 +1,466 @@
-use std::iter::repeat;
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Write,
+    str::FromStr,
+};
+
+use advent_of_code::helpers::{
+    animate::{AnimateArgs, Animator},
+    visualize::{
+        write_png, Bounds, Frame, GifRecorder, Palette, RenderArgs, BLACK, GOLD, GRAY, RED, WHITE,
+    },
+};
+
+/// The vector the head moves by in a single step.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Debug, Clone, Copy)]
+pub struct Direction {
+    pub dx: i32,
+    pub dy: i32,
+}
 
-use itertools::Itertools;
+impl Direction {
+    pub const RIGHT: Direction = Direction { dx: 1, dy: 0 };
+    pub const LEFT: Direction = Direction { dx: -1, dy: 0 };
+    pub const UP: Direction = Direction { dx: 0, dy: 1 };
+    pub const DOWN: Direction = Direction { dx: 0, dy: -1 };
 
-#[derive(PartialEq, Eq, PartialOrd, Ord, Debug, Clone, Copy)]
-pub enum Direction {
-    Right,
-    Left,
-    Up,
-    Down,
-}
-
-impl From<char> for Direction {
-    fn from(c: char) -> Self {
-        match c {
-            'R' => Direction::Right,
-            'L' => Direction::Left,
-            'U' => Direction::Up,
-            'D' => Direction::Down,
-            _ => unimplemented!("Invalid move"),
+    pub fn reversed(&self) -> Direction {
+        Direction {
+            dx: -self.dx,
+            dy: -self.dy,
+        }
+    }
+}
+
+impl FromStr for Direction {
+    type Err = String;
+
+    /// Either a word like `up`, or a combination of `R`, `L`, `U` and `D` such as `UR` for a
+    /// diagonal step.
+    fn from_str(token: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid direction {:?}", token);
+        let token = token.to_ascii_uppercase();
+
+        match token.as_str() {
+            "RIGHT" => return Ok(Direction::RIGHT),
+            "LEFT" => return Ok(Direction::LEFT),
+            "UP" => return Ok(Direction::UP),
+            "DOWN" => return Ok(Direction::DOWN),
+            _ => {}
         }
+
+        let mut direction = Direction { dx: 0, dy: 0 };
+        for letter in token.chars() {
+            let (axis, unit) = match letter {
+                'R' => (&mut direction.dx, 1),
+                'L' => (&mut direction.dx, -1),
+                'U' => (&mut direction.dy, 1),
+                'D' => (&mut direction.dy, -1),
+                _ => return Err(invalid()),
+            };
+
+            // Every axis can only be given once, so `RL` or `UU` aren't directions
+            if *axis != 0 {
+                return Err(invalid());
+            }
+            *axis = unit;
+        }
+
+        if direction == (Direction { dx: 0, dy: 0 }) {
+            return Err(invalid());
+        }
+
+        Ok(direction)
     }
 }
 
 type Move = (Direction, usize);
 
-pub fn parse_input(input: &str) -> Vec<Move> {
+/// Parses one move per line. Negative distances move the opposite way and zero distances are
+/// kept as moves that do nothing.
+pub fn parse_input(input: &str) -> Result<Vec<Move>, String> {
     input
         .lines()
-        .map(|line| {
-            let split = line.split(" ").collect::<Vec<&str>>();
-            (
-                Direction::from(split[0].chars().next().unwrap()),
-                split[1].parse::<usize>().unwrap(),
-            )
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| {
+            let error = |reason: String| format!("line {}: {}", index + 1, reason);
+
+            match line.split_whitespace().collect::<Vec<&str>>()[..] {
+                [direction, distance] => {
+                    let direction = direction.parse::<Direction>().map_err(error)?;
+                    let distance = distance
+                        .parse::<i64>()
+                        .map_err(|_| error(format!("invalid distance {:?}", distance)))?;
+
+                    if distance < 0 {
+                        Ok((direction.reversed(), distance.unsigned_abs() as usize))
+                    } else {
+                        Ok((direction, distance as usize))
+                    }
+                }
+                _ => Err(error(format!(
+                    "expected a direction and a distance, found {:?}",
+                    line
+                ))),
+            }
         })
         .collect()
 }
 
-pub fn get_direction_list(moves: &Vec<Move>) -> Vec<Direction> {
-    moves
-        .iter()
-        .flat_map(|(direction, amount)| repeat(direction).take(*amount).map(|_| *direction))
-        .collect()
+fn parse_or_report(input: &str) -> Option<Vec<Move>> {
+    parse_input(input)
+        .map_err(|e| eprintln!("Invalid moves: {}", e))
+        .ok()
 }
 
 type Coordinate = (i32, i32);
+type FrameCallback<'a> = &'a mut dyn FnMut(&[Coordinate]);
 
-#[derive(Debug, Clone, Copy)]
-pub struct RopeState {
-    pub head: Coordinate,
-    pub tail: Coordinate,
+fn step(knot: Coordinate, direction: Direction) -> Coordinate {
+    (knot.0 + direction.dx, knot.1 + direction.dy)
 }
 
-impl Default for RopeState {
-    fn default() -> Self {
-        RopeState {
-            head: (0, 0),
-            tail: (0, 0),
+// Where `tail` ends up after `head` moved. Once they stop touching, the tail moves one cell
+// towards the head on every axis they differ in, until it touches the head again. Heads that
+// move a single cell, diagonals included, never need more than one of those moves.
+fn follow(head: Coordinate, mut tail: Coordinate) -> Coordinate {
+    loop {
+        let diff = (head.0 - tail.0, head.1 - tail.1);
+
+        if diff.0.abs() <= 1 && diff.1.abs() <= 1 {
+            return tail;
         }
+
+        tail = (tail.0 + diff.0.signum(), tail.1 + diff.1.signum());
     }
 }
 
-fn is_adjacent(first: &Coordinate, second: &Coordinate) -> bool {
-    if first == second {
-        return true;
+/// Moves a rope of `knots` knots and returns every cell its tail visited. Only the current
+/// knot positions are kept, `on_frame` receives them after every step.
+pub fn simulate(
+    moves: &[Move],
+    knots: usize,
+    mut on_frame: Option<FrameCallback>,
+) -> HashSet<Coordinate> {
+    let mut rope = vec![(0, 0); knots.max(1)];
+    let mut visited = HashSet::from([(0, 0)]);
+
+    for &(direction, amount) in moves {
+        for _ in 0..amount {
+            rope[0] = step(rope[0], direction);
+
+            for i in 1..rope.len() {
+                let next = follow(rope[i - 1], rope[i]);
+
+                // The rest of the rope won't move either
+                if next == rope[i] {
+                    break;
+                }
+
+                rope[i] = next;
+            }
+
+            visited.insert(*rope.last().unwrap());
+
+            if let Some(on_frame) = on_frame.as_mut() {
+                on_frame(&rope);
+            }
+        }
     }
 
-    let (x1, y1) = first;
-    let (x2, y2) = second;
+    visited
+}
 
-    if (x1 - x2).abs() <= 1 && (y1 - y2).abs() <= 1 {
-        return true;
-    } else {
-        false
+pub fn part_one(input: &str) -> Option<u32> {
+    let moves = parse_or_report(input)?;
+
+    Some(simulate(&moves, 2, None).len() as u32)
+}
+
+pub fn part_two(input: &str) -> Option<u32> {
+    let moves = parse_or_report(input)?;
+
+    Some(simulate(&moves, 10, None).len() as u32)
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct KnotStats {
+    pub visited: HashSet<Coordinate>,
+    // Furthest the knot got from the start, counting diagonal steps as one like the rope does
+    pub max_distance: i32,
+}
+
+/// Cells visited by every knot of a rope of `knots` knots, the head first.
+pub fn knot_stats(moves: &[Move], knots: usize) -> Vec<KnotStats> {
+    let mut stats = vec![
+        KnotStats {
+            visited: HashSet::from([(0, 0)]),
+            max_distance: 0,
+        };
+        knots.max(1)
+    ];
+
+    simulate(
+        moves,
+        knots,
+        Some(&mut |rope: &[Coordinate]| {
+            for (knot, stats) in rope.iter().zip(stats.iter_mut()) {
+                stats.visited.insert(*knot);
+                stats.max_distance = stats.max_distance.max(knot.0.abs().max(knot.1.abs()));
+            }
+        }),
+    );
+
+    stats
+}
+
+// Knots are labelled like in the puzzle: `H` for the head, then `T` or their index
+fn knot_label(index: usize, knots: usize) -> char {
+    match index {
+        0 => 'H',
+        1 if knots == 2 => 'T',
+        _ => char::from_digit(index as u32, 36).unwrap_or('*'),
     }
 }
 
-fn get_next_state(state: &RopeState, direction: Direction) -> RopeState {
-    let (x, y) = state.head;
-    let new_head = match direction {
-        Direction::Down => (x, y - 1),
-        Direction::Up => (x, y + 1),
-        Direction::Left => (x - 1, y),
-        Direction::Right => (x + 1, y),
+// Up is drawn upwards, like in the puzzle
+fn as_point(knot: &Coordinate) -> (i64, i64) {
+    (knot.0 as i64, -knot.1 as i64)
+}
+
+// Draws `cells` on a grid of `.`, later cells are drawn on top of earlier ones
+fn format_grid(cells: impl IntoIterator<Item = (Coordinate, char)>) -> String {
+    let cells = cells
+        .into_iter()
+        .map(|(knot, label)| (as_point(&knot), label))
+        .collect::<HashMap<(i64, i64), char>>();
+    let bounds = match Bounds::of(cells.keys().copied()) {
+        Some(bounds) => bounds,
+        None => return String::new(),
     };
 
-    let adjacent = is_adjacent(&new_head, &state.tail);
-    let diff = (new_head.0 - state.tail.0, new_head.1 - state.tail.1);
+    (bounds.min_y..=bounds.max_y)
+        .map(|y| {
+            (bounds.min_x..=bounds.max_x)
+                .map(|x| *cells.get(&(x, y)).unwrap_or(&'.'))
+                .collect::<String>()
+                + "\n"
+        })
+        .collect()
+}
+
+/// The cells visited by a knot as `#`, with the start as `s`.
+pub fn format_trail(visited: &HashSet<Coordinate>) -> String {
+    format_grid(
+        visited
+            .iter()
+            .map(|&knot| (knot, '#'))
+            .chain([((0, 0), 's')]),
+    )
+}
+
+/// Every knot of the rope, within `bounds` so that all steps line up.
+pub fn format_layout(rope: &[Coordinate], bounds: &[Coordinate; 2]) -> String {
+    let corners = bounds.iter().map(|&corner| (corner, '.'));
+    let start = [((0, 0), 's')];
+    // Back to front, so knots in front cover the ones behind them
+    let knots = rope
+        .iter()
+        .enumerate()
+        .rev()
+        .map(|(i, &knot)| (knot, knot_label(i, rope.len())));
+
+    format_grid(corners.chain(start).chain(knots))
+}
+
+pub fn format_stats(stats: &[KnotStats]) -> String {
+    let mut table = format!("{:<6} {:>8} {:>12}\n", "Knot", "Visited", "Max distance");
+
+    for (i, knot) in stats.iter().enumerate() {
+        writeln!(
+            table,
+            "{:<6} {:>8} {:>12}",
+            knot_label(i, stats.len()),
+            knot.visited.len(),
+            knot.max_distance
+        )
+        .unwrap();
+    }
 
-    if adjacent {
-        RopeState {
-            head: new_head,
-            tail: state.tail,
+    table
+}
+
+// `--knots <n>` sets the length of the rope (default 10). `--trail <knot>` draws the cells
+// visited by a knot (0 is the head), `--dump` draws the rope after every step and `--stats`
+// lists the cells visited by every knot.
+fn report(input: &str) {
+    let mut args = pico_args::Arguments::from_env();
+    let dump = args.contains("--dump");
+    let stats = args.contains("--stats");
+    let (knots, trail): (Option<usize>, Option<usize>) = match args
+        .opt_value_from_str("--knots")
+        .and_then(|knots| Ok((knots, args.opt_value_from_str("--trail")?)))
+    {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("Not reporting: {}", e);
+            return;
         }
-    } else {
-        RopeState {
-            head: new_head,
-            tail: (
-                state.tail.0 + diff.0.signum(),
-                state.tail.1 + diff.1.signum(),
-            ),
+    };
+    let knots = knots.unwrap_or(10);
+
+    if trail.is_none() && !dump && !stats {
+        return;
+    }
+
+    let moves = match parse_input(input) {
+        Ok(moves) => moves,
+        Err(_) => return,
+    };
+
+    if dump {
+        // The head moves furthest, so its positions bound every layout
+        let mut bounds = [(0, 0); 2];
+        simulate(
+            &moves,
+            1,
+            Some(&mut |rope: &[Coordinate]| {
+                bounds[0] = (bounds[0].0.min(rope[0].0), bounds[0].1.min(rope[0].1));
+                bounds[1] = (bounds[1].0.max(rope[0].0), bounds[1].1.max(rope[0].1));
+            }),
+        );
+
+        let mut steps = 0;
+        simulate(
+            &moves,
+            knots,
+            Some(&mut |rope: &[Coordinate]| {
+                steps += 1;
+                println!("Step {}:\n{}", steps, format_layout(rope, &bounds));
+            }),
+        );
+    }
+
+    let all_stats = knot_stats(&moves, knots);
+
+    if let Some(knot) = trail {
+        match all_stats.get(knot) {
+            Some(knot_stats) => print!("{}", format_trail(&knot_stats.visited)),
+            None => eprintln!("The rope only has {} knots", all_stats.len()),
         }
     }
-}
 
-pub fn part_one(input: &str) -> Option<u32> {
-    let moves = parse_input(input);
-    let directions = get_direction_list(&moves);
-    let initial = RopeState::default();
+    if stats {
+        print!("{}", format_stats(&all_stats));
+    }
+}
 
-    let mut states = directions
+fn draw(bounds: Bounds, visited: &HashSet<Coordinate>, knots: &[Coordinate]) -> Frame {
+    let trail = visited.iter().map(|knot| (as_point(knot), 1));
+    let origin = [((0, 0), 2)];
+    // Draw the rope back to front, so the head ends up on top
+    let rope = knots
         .iter()
-        .scan(initial, |acc, direction| {
-            let next = get_next_state(&acc, *direction);
-            *acc = next.clone();
-            Some(next)
-        })
-        .collect::<Vec<RopeState>>();
-    states.insert(0, initial);
+        .rev()
+        .enumerate()
+        .map(|(i, knot)| (as_point(knot), if i == knots.len() - 1 { 4 } else { 3 }));
 
-    Some(states.iter().map(|state| state.tail).unique().count() as u32)
+    Frame::from_points_in(bounds, trail.chain(origin).chain(rope))
 }
 
-pub fn part_two(input: &str) -> Option<u32> {
-    let moves = parse_input(input);
-    let directions = get_direction_list(&moves);
-    let initial = repeat(RopeState::default())
-        .take(10)
-        .collect::<Vec<RopeState>>();
+fn render(input: &str, args: &RenderArgs) {
+    if !args.is_enabled() {
+        return;
+    }
 
-    let mut states = directions
-        .iter()
-        .scan(initial.clone(), |acc, direction| {
-            let first = acc[0];
-            let next = get_next_state(&first, *direction);
+    let moves = match parse_input(input) {
+        Ok(moves) => moves,
+        Err(_) => return,
+    };
 
-            let mut updated = acc
-                .iter()
-                .skip(1)
-                .scan(next, |acc2, curr| {
-                    let new = RopeState {
-                        head: acc2.tail,
-                        tail: curr.tail,
-                    };
-
-                    let adjacent = is_adjacent(&new.head, &new.tail);
-
-                    if adjacent {
-                        *acc2 = new.clone();
-                        Some(new)
-                    } else {
-                        let diff = (new.head.0 - new.tail.0, new.head.1 - new.tail.1);
+    // The head moves furthest, so its positions bound the whole animation. Up is drawn upwards.
+    let mut bounds = Bounds::of([(0, 0)]).unwrap();
+    simulate(
+        &moves,
+        1,
+        Some(&mut |knots: &[Coordinate]| {
+            bounds = bounds.include(as_point(&knots[0]));
+        }),
+    );
+
+    let palette = Palette::new(&[BLACK, GRAY, WHITE, GOLD, RED]);
+    // Frames are only built when there is a GIF to record them into
+    let mut recorder = args
+        .gif
+        .as_ref()
+        .map(|_| GifRecorder::new(palette.clone(), 4, 5).every(args.every));
+    let mut visited = HashSet::from([(0, 0)]);
+    let mut last = vec![];
+
+    let trail = simulate(
+        &moves,
+        10,
+        Some(&mut |knots: &[Coordinate]| {
+            if let Some(recorder) = recorder.as_mut() {
+                visited.insert(*knots.last().unwrap());
+                recorder.push_with(|| draw(bounds, &visited, knots));
+            }
+            last = knots.to_vec();
+        }),
+    );
+
+    if let Some(path) = &args.png {
+        if let Err(e) = write_png(path, &draw(bounds, &trail, &last), &palette, 4) {
+            eprintln!("Failed to write {:?}: {}", path, e);
+            return;
+        }
+    }
+
+    if let (Some(path), Some(recorder)) = (&args.gif, &recorder) {
+        if let Err(e) = recorder.write(path) {
+            eprintln!("Failed to write {:?}: {}", path, e);
+        }
+    }
+}
 
-                        let new = RopeState {
-                            head: acc2.tail,
-                            tail: (new.tail.0 + diff.0.signum(), new.tail.1 + diff.1.signum()),
-                        };
+fn animate(input: &str, args: &AnimateArgs) {
+    if !args.enabled {
+        return;
+    }
 
-                        *acc2 = new.clone();
-                        Some(new)
-                    }
-                })
-                .collect::<Vec<RopeState>>();
+    let moves = match parse_input(input) {
+        Ok(moves) => moves,
+        Err(_) => return,
+    };
+    let mut animator = Animator::new(args.delay);
+    let mut visited = HashSet::from([(0, 0)]);
 
-            updated.insert(0, next);
+    simulate(
+        &moves,
+        10,
+        Some(&mut |knots: &[Coordinate]| {
+            visited.insert(*knots.last().unwrap());
 
-            *acc = updated.clone();
-            Some(updated)
-        })
-        .collect::<Vec<Vec<RopeState>>>();
-    states.insert(0, initial);
+            let trail = visited.iter().map(|knot| (as_point(knot), '#'));
+            let rope = knots
+                .iter()
+                .enumerate()
+                .rev()
+                .map(|(i, knot)| (as_point(knot), knot_label(i, knots.len())));
 
-    Some(
-        states
-            .iter()
-            .map(|state| state.iter().last().unwrap().head)
-            .unique()
-            .count() as u32,
-    )
+            animator.draw(trail.chain([((0, 0), 's')]).chain(rope));
+        }),
+    );
 }
 
 fn main() {
     let input = &advent_of_code::read_file("inputs", 9);
     advent_of_code::solve!(1, part_one, input);
     advent_of_code::solve!(2, part_two, input);
+    render(input, &RenderArgs::from_env());
+    animate(input, &AnimateArgs::from_env());
+    report(input);
 }
 
 #[cfg(test)]
@@ -200,5 +477,94 @@ mod tests {
     fn test_part_two() {
         let input = advent_of_code::read_file("examples", 9);
         assert_eq!(part_two(&input), Some(1));
+        assert_eq!(
+            part_two("R 5\nU 8\nL 8\nD 3\nR 17\nD 10\nL 25\nU 20"),
+            Some(36)
+        );
+    }
+
+    #[test]
+    fn test_knot_counts() {
+        let moves = parse_input("R 3\nU 2").unwrap();
+
+        // A single knot is its own tail
+        assert_eq!(simulate(&moves, 1, None).len(), 6);
+        assert_eq!(simulate(&moves, 2, None).len(), 4);
+        assert_eq!(simulate(&moves, 4, None), HashSet::from([(0, 0), (1, 1)]));
+    }
+
+    #[test]
+    fn test_knot_stats() {
+        let moves = parse_input("R 5\nU 8\nL 8\nD 3\nR 17\nD 10\nL 25\nU 20").unwrap();
+        let stats = knot_stats(&moves, 10);
+
+        assert_eq!(stats.len(), 10);
+        assert_eq!(stats[9].visited.len(), 36);
+        assert_eq!(stats[0].max_distance, 15);
+        assert_eq!(
+            format_trail(&stats[9].visited),
+            [
+                "#.....................",
+                "#.............###.....",
+                "#............#...#....",
+                ".#..........#.....#...",
+                "..#..........#.....#..",
+                "...#........#.......#.",
+                "....#......s.........#",
+                ".....#..............#.",
+                "......#............#..",
+                ".......#..........#...",
+                "........#........#....",
+                ".........########.....",
+                "",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn test_layout() {
+        let moves = parse_input("R 4\nU 1").unwrap();
+        let mut last = vec![];
+        simulate(
+            &moves,
+            10,
+            Some(&mut |rope: &[Coordinate]| last = rope.to_vec()),
+        );
+
+        assert_eq!(
+            format_layout(&last, &[(0, 0), (5, 2)]),
+            ["......", "....H.", "4321..", ""].join("\n")
+        );
+    }
+
+    #[test]
+    fn test_directions() {
+        assert_eq!("UR".parse(), Ok(Direction { dx: 1, dy: 1 }));
+        assert_eq!("ld".parse(), Ok(Direction { dx: -1, dy: -1 }));
+        assert_eq!("Up".parse(), Ok(Direction::UP));
+        assert!("RL".parse::<Direction>().is_err());
+        assert!("X".parse::<Direction>().is_err());
+        assert!("".parse::<Direction>().is_err());
+
+        assert_eq!(
+            parse_input("R -2\nU 0"),
+            Ok(vec![(Direction::LEFT, 2), (Direction::UP, 0)])
+        );
+        assert_eq!(
+            parse_input("R 1\nR x"),
+            Err("line 2: invalid distance \"x\"".to_string())
+        );
+        assert_eq!(part_one("Q 1"), None);
+    }
+
+    #[test]
+    fn test_diagonal_moves() {
+        let moves = parse_input("UR 3\nDL 1").unwrap();
+        let visited = simulate(&moves, 2, None);
+
+        assert_eq!(visited, HashSet::from([(0, 0), (1, 1), (2, 2)]));
+        // Tails catch up with heads that jump several cells at once
+        assert_eq!(follow((4, 1), (0, 0)), (3, 1));
     }
 }
@@ -1,98 +1,250 @@
+use advent_of_code::{
+    helpers::animate::{AnimateArgs, Animator},
+    ANSI_BOLD, ANSI_RESET,
+};
 use itertools::Itertools;
 
+// Registers are named by a single letter, `x` is the one the CRT follows
+pub const REGISTER_NAMES: [char; 4] = ['x', 'y', 'z', 'w'];
+pub const X: usize = 0;
+
+pub type Registers = [i32; REGISTER_NAMES.len()];
+
 #[derive(PartialEq, Eq, PartialOrd, Ord, Debug, Clone, Copy)]
-pub enum Instruction {
-    Noop,
-    Addx(i32),
+pub enum Syntax {
+    // `noop`
+    Bare,
+    // `addx 3`, the register is the last letter of the mnemonic
+    RegisterSuffix,
+    // `jmp -2`
+    Value,
+    // `jnz x -2`
+    RegisterAndValue,
 }
 
-pub fn parse_input(input: &str) -> Vec<Instruction> {
-    input
-        .lines()
-        .map(|line| {
-            let split = line.split(" ").collect_vec();
+/// An entry of the instruction table. `effect` runs at the end of the last cycle of the
+/// instruction and returns how far the program counter moves. Arithmetic wraps on overflow so
+/// programs that loop can't panic.
+#[derive(Debug, Clone, Copy)]
+pub struct Opcode {
+    pub name: &'static str,
+    pub syntax: Syntax,
+    pub cycles: usize,
+    pub effect: fn(&mut Registers, usize, i32) -> isize,
+}
+
+pub const INSTRUCTION_TABLE: &[Opcode] = &[
+    Opcode {
+        name: "noop",
+        syntax: Syntax::Bare,
+        cycles: 1,
+        effect: |_, _, _| 1,
+    },
+    Opcode {
+        name: "add",
+        syntax: Syntax::RegisterSuffix,
+        cycles: 2,
+        effect: |registers, register, value| {
+            registers[register] = registers[register].wrapping_add(value);
+            1
+        },
+    },
+    Opcode {
+        name: "mul",
+        syntax: Syntax::RegisterSuffix,
+        cycles: 2,
+        effect: |registers, register, value| {
+            registers[register] = registers[register].wrapping_mul(value);
+            1
+        },
+    },
+    Opcode {
+        name: "jmp",
+        syntax: Syntax::Value,
+        cycles: 1,
+        effect: |_, _, offset| offset as isize,
+    },
+    Opcode {
+        name: "jnz",
+        syntax: Syntax::RegisterAndValue,
+        cycles: 1,
+        effect: |registers, register, offset| {
+            if registers[register] != 0 {
+                offset as isize
+            } else {
+                1
+            }
+        },
+    },
+];
+
+#[derive(Debug, Clone, Copy)]
+pub struct Instruction {
+    pub opcode: &'static Opcode,
+    pub register: usize,
+    pub value: i32,
+}
 
-            match split[0] {
-                "noop" => Instruction::Noop,
-                "addx" => Instruction::Addx(split[1].parse().unwrap()),
-                _ => unreachable!(),
+fn parse_register(name: &str) -> Result<usize, String> {
+    let mut chars = name.chars();
+
+    match (chars.next(), chars.next()) {
+        (Some(letter), None) => REGISTER_NAMES
+            .iter()
+            .position(|&register| register == letter)
+            .ok_or_else(|| format!("unknown register {:?}", name)),
+        _ => Err(format!("unknown register {:?}", name)),
+    }
+}
+
+fn parse_instruction(line: &str) -> Result<Instruction, String> {
+    let words = line.split_whitespace().collect_vec();
+    let mnemonic = *words.first().ok_or("empty instruction")?;
+    let parse_value = |value: &str| {
+        value
+            .parse::<i32>()
+            .map_err(|_| format!("invalid value {:?}", value))
+    };
+
+    for opcode in INSTRUCTION_TABLE {
+        let instruction = |register, value| Instruction {
+            opcode,
+            register,
+            value,
+        };
+
+        match (opcode.syntax, &words[1..]) {
+            (Syntax::Bare, []) if mnemonic == opcode.name => return Ok(instruction(X, 0)),
+            (Syntax::RegisterSuffix, [value]) if mnemonic.starts_with(opcode.name) => {
+                let register = parse_register(&mnemonic[opcode.name.len()..])?;
+                return Ok(instruction(register, parse_value(value)?));
+            }
+            (Syntax::Value, [value]) if mnemonic == opcode.name => {
+                return Ok(instruction(X, parse_value(value)?));
+            }
+            (Syntax::RegisterAndValue, [register, value]) if mnemonic == opcode.name => {
+                return Ok(instruction(parse_register(register)?, parse_value(value)?));
             }
+            _ => {}
+        }
+    }
+
+    Err(format!("unknown instruction {:?}", line))
+}
+
+pub fn parse_input(input: &str) -> Result<Vec<Instruction>, String> {
+    input
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| {
+            parse_instruction(line).map_err(|e| format!("line {}: {}", index + 1, e))
         })
-        .collect_vec()
+        .collect()
 }
 
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone)]
 pub struct VMState {
     pub cycle: usize,
     pub program_counter: usize,
-    // How many cycles left before executing the next instruction
+    // How many cycles have already been spent on the current instruction
     pub pending_cycles: usize,
-    pub register_x: i32,
-    pub instructions: Vec<Instruction>,
+    pub registers: Registers,
     pub halted: bool,
 }
 
 impl VMState {
-    pub fn run_cycle(&self) -> VMState {
+    pub fn run_cycle(&self, program: &[Instruction]) -> VMState {
         let mut new_state = self.clone();
-        let current_instruction = new_state.instructions[new_state.program_counter];
+        let current_instruction = program[new_state.program_counter];
+        let opcode = current_instruction.opcode;
 
-        match current_instruction {
-            Instruction::Noop => {
-                new_state.program_counter += 1;
-            }
-            Instruction::Addx(operand) if new_state.pending_cycles == 1 => {
-                new_state.register_x += operand;
-                new_state.program_counter += 1;
-                new_state.pending_cycles = 0;
-            }
-            Instruction::Addx(_) => {
-                new_state.pending_cycles = 1;
+        new_state.pending_cycles += 1;
+
+        if new_state.pending_cycles == opcode.cycles {
+            let offset = (opcode.effect)(
+                &mut new_state.registers,
+                current_instruction.register,
+                current_instruction.value,
+            );
+
+            // Jumping before the start of the program halts it just like running off its end
+            match new_state.program_counter.checked_add_signed(offset) {
+                Some(program_counter) => new_state.program_counter = program_counter,
+                None => new_state.halted = true,
             }
+            new_state.pending_cycles = 0;
         }
 
         new_state.cycle += 1;
 
-        if new_state.program_counter >= new_state.instructions.len() {
+        if new_state.program_counter >= program.len() {
             new_state.halted = true;
         }
 
         new_state
     }
 
-    pub fn new(instructions: Vec<Instruction>) -> Self {
+    pub fn new(program: &[Instruction]) -> Self {
+        let mut registers = [0; REGISTER_NAMES.len()];
+        registers[X] = 1;
+
         Self {
             cycle: 0,
             program_counter: 0,
             pending_cycles: 0,
-            register_x: 1,
-            halted: instructions.len() == 0,
-            instructions,
+            registers,
+            halted: program.is_empty(),
         }
     }
-}
 
-pub fn part_one(input: &str) -> Option<i32> {
-    let instructions = parse_input(input);
+    pub fn register_x(&self) -> i32 {
+        self.registers[X]
+    }
+}
 
+/// The state during each of the first `cycles` cycles, and the final state if the program
+/// halts before then. Programs may loop forever, so they never run for longer.
+pub fn trace(program: &[Instruction], cycles: usize) -> Vec<VMState> {
     let mut states = Vec::new();
-    let mut current_state = VMState::new(instructions);
+    let mut current_state = VMState::new(program);
 
     states.push(current_state.clone());
 
-    while !current_state.halted {
-        current_state = current_state.run_cycle();
+    while !current_state.halted && current_state.cycle < cycles {
+        current_state = current_state.run_cycle(program);
         states.push(current_state.clone());
     }
 
+    states
+}
+
+fn parse_or_report(input: &str) -> Option<Vec<Instruction>> {
+    parse_input(input)
+        .map_err(|e| eprintln!("Invalid program: {}", e))
+        .ok()
+}
+
+pub fn part_one(input: &str) -> Option<i64> {
+    let program = parse_or_report(input)?;
+    let states = trace(&program, 220);
+
     Some(
-        vec![20, 60, 100, 140, 180, 220]
+        [20, 60, 100, 140, 180, 220]
             .iter()
-            .map(|cycle| states[cycle - 1].register_x * (*cycle as i32))
-            .sum::<i32>(),
+            // After halting, X keeps its last value
+            .map(|&cycle| {
+                states[(cycle - 1).min(states.len() - 1)].register_x() as i64 * cycle as i64
+            })
+            .sum::<i64>(),
     )
 }
 
+// Whether the sprite covers `col_index` while the beam is drawing it
+fn is_lit(col_index: usize, register_x: i32) -> bool {
+    (col_index as i64 - register_x as i64).abs() <= 1
+}
+
 pub fn draw_frame(states: Vec<VMState>) -> String {
     let frame = (0..6).map(|_| (0..40).collect_vec()).collect_vec();
 
@@ -103,10 +255,8 @@ pub fn draw_frame(states: Vec<VMState>) -> String {
             row.iter()
                 .enumerate()
                 .map(|(col_index, _)| {
-                    let current_state = &states[row_index * 40 + col_index];
-                    if (col_index + 1) >= current_state.register_x as usize
-                        && (col_index + 1) < current_state.register_x as usize + 3
-                    {
+                    let position = (row_index * 40 + col_index).min(states.len() - 1);
+                    if is_lit(col_index, states[position].register_x()) {
                         '#'
                     } else {
                         '.'
@@ -124,27 +274,76 @@ pub fn draw_frame(states: Vec<VMState>) -> String {
 }
 
 pub fn part_two(input: &str) -> Option<String> {
-    let instructions = parse_input(input);
+    let program = parse_or_report(input)?;
+    let states = trace(&program, 240);
 
-    let mut states = Vec::new();
-    let mut current_state = VMState::new(instructions);
+    let frame = draw_frame(states);
 
-    states.push(current_state.clone());
+    Some(frame)
+}
 
-    while !current_state.halted {
-        current_state = current_state.run_cycle();
-        states.push(current_state.clone());
+fn animate(input: &str, args: &AnimateArgs) {
+    if !args.enabled {
+        return;
     }
 
-    let frame = draw_frame(states);
+    let program = match parse_input(input) {
+        Ok(program) => program,
+        Err(_) => return,
+    };
+    let mut current_state = VMState::new(&program);
+    let mut animator = Animator::new(args.delay);
+    let mut pixels = String::new();
 
-    Some(frame)
+    for position in 0..240 {
+        let (row_index, col_index) = (position / 40, position % 40);
+
+        if col_index == 0 && row_index > 0 {
+            pixels.push('\n');
+        }
+
+        pixels.push(if is_lit(col_index, current_state.register_x()) {
+            '#'
+        } else {
+            '.'
+        });
+
+        // Highlight the beam and show where the sprite currently is
+        let sprite = (0..40)
+            .map(|col| {
+                if is_lit(col, current_state.register_x()) {
+                    '#'
+                } else {
+                    ' '
+                }
+            })
+            .collect::<String>();
+        let (drawn, beam) = pixels.split_at(pixels.len() - 1);
+
+        animator.draw_text(&format!(
+            "{}{}{}{}\n\nSprite: {}\nCycle {} | X = {}",
+            drawn,
+            ANSI_BOLD,
+            beam,
+            ANSI_RESET,
+            sprite,
+            position + 1,
+            current_state.register_x(),
+        ));
+
+        if current_state.halted {
+            break;
+        }
+
+        current_state = current_state.run_cycle(&program);
+    }
 }
 
 fn main() {
     let input = &advent_of_code::read_file("inputs", 10);
     advent_of_code::solve!(1, part_one, input);
     advent_of_code::solve!(2, part_two, input);
+    animate(input, &AnimateArgs::from_env());
 }
 
 #[cfg(test)]
@@ -168,4 +367,45 @@ mod tests {
         let input = advent_of_code::read_file("examples", 10);
         assert_eq!(part_two(&input), Some(out.to_string()));
     }
+
+    #[test]
+    fn test_extended_instructions() {
+        let program = parse_input("addy 3\naddx 2\naddy -1\njnz y -2\nmulx 3").unwrap();
+        let states = trace(&program, 1000);
+        let last = states.last().unwrap();
+
+        assert!(last.halted);
+        assert_eq!(last.cycle, 2 + 3 * (2 + 2 + 1) + 2);
+        assert_eq!(last.registers, [21, 0, 0, 0]);
+        // `addx` only takes effect at the end of its second cycle
+        assert_eq!(states[3].register_x(), 1);
+        assert_eq!(states[4].register_x(), 3);
+    }
+
+    #[test]
+    fn test_invalid_and_endless_programs() {
+        assert_eq!(
+            parse_input("noop\naddq 1").unwrap_err(),
+            "line 2: unknown register \"q\""
+        );
+        assert_eq!(
+            parse_input("jmp").unwrap_err(),
+            "line 1: unknown instruction \"jmp\""
+        );
+        assert_eq!(trace(&parse_input("jmp 0").unwrap(), 50).len(), 51);
+        assert_eq!(part_one("addx 2\njmp 0"), Some(3 * 720));
+    }
+
+    #[test]
+    fn test_overflow() {
+        // X starts at 1, so the first addition already wraps around
+        let states = trace(&parse_input("addx 2147483647\naddx 1").unwrap(), 10);
+        assert_eq!(states.last().unwrap().register_x(), i32::MIN + 1);
+
+        // Multiplies X by 10 forever
+        let program = parse_input("addy 1\nmulx 10\njnz y -1").unwrap();
+        assert_eq!(trace(&program, 1000).len(), 1001);
+        assert!(part_one("addx 2147483646\nnoop").is_some());
+        assert!(part_two("addx -2147483647\naddx -2\nmulx 7").is_some());
+    }
 }
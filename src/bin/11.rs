@@ -1,5 +1,6 @@
 use std::cell::RefCell;
 
+use advent_of_code::helpers::cycle::find_cycle;
 use itertools::Itertools;
 
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
@@ -171,11 +172,11 @@ pub fn perform_operation(operation: (Operation, Operand, Operand), old: u128) ->
 
 pub fn perform_test(test: Test, worry: u128) -> bool {
     match test {
-        Test::Divisible(divisor) => worry % divisor == 0,
+        Test::Divisible(divisor) => worry.is_multiple_of(divisor),
     }
 }
 
-pub fn run_round(monkeys: &Vec<Monkey>) -> Vec<Monkey> {
+pub fn run_round(monkeys: &[Monkey]) -> Vec<Monkey> {
     // STFU Rust's borrow checker
     let new_monkeys = monkeys
         .iter()
@@ -221,7 +222,7 @@ pub fn run_round(monkeys: &Vec<Monkey>) -> Vec<Monkey> {
         .collect_vec()
 }
 
-pub fn run_round_2(monkeys: &Vec<Monkey>) -> Vec<Monkey> {
+pub fn run_round_2(monkeys: &[Monkey]) -> Vec<Monkey> {
     let wrap = monkeys
         .iter()
         .map(|m| match m.test {
@@ -278,7 +279,7 @@ pub fn run_round_2(monkeys: &Vec<Monkey>) -> Vec<Monkey> {
 pub fn part_one(input: &str) -> Option<u128> {
     let monkeys = parse_input(input);
     let mut final_state = (0..20).fold(monkeys, |state, _| run_round(&state));
-    final_state.sort_by(|a, b| a.inspect_count.cmp(&b.inspect_count));
+    final_state.sort_by_key(|monkey| monkey.inspect_count);
 
     Some(
         final_state.last().unwrap().inspect_count
@@ -288,13 +289,32 @@ pub fn part_one(input: &str) -> Option<u128> {
 
 pub fn part_two(input: &str) -> Option<u128> {
     let monkeys = parse_input(input);
-    let mut final_state = (0..10000).fold(monkeys, |state, _| run_round_2(&state));
-    final_state.sort_by(|a, b| a.inspect_count.cmp(&b.inspect_count));
-
-    Some(
-        final_state.last().unwrap().inspect_count
-            * final_state[final_state.len() - 2].inspect_count,
-    )
+    let monkey_count = monkeys.len();
+
+    // Inspect counts only ever go up, so only the items held by each monkey decide what happens next
+    let history = find_cycle(
+        monkeys,
+        |a, b| {
+            a.iter()
+                .zip(b)
+                .all(|(a, b)| a.starting_items == b.starting_items)
+        },
+        |state| run_round_2(state),
+        |state| {
+            state
+                .iter()
+                .map(|monkey| monkey.inspect_count)
+                .collect_vec()
+        },
+        10000,
+    );
+
+    let mut inspect_counts = (0..monkey_count)
+        .map(|index| history.extrapolate(10000, |counts| counts[index]))
+        .collect::<Option<Vec<u128>>>()?;
+    inspect_counts.sort();
+
+    Some(inspect_counts[monkey_count - 1] * inspect_counts[monkey_count - 2])
 }
 
 fn main() {
@@ -1,10 +1,13 @@
+use advent_of_code::helpers::{
+    search::bfs_multi,
+    visualize::{write_png, Frame, GifRecorder, Palette, RenderArgs, BLACK, GOLD, GREEN, RED},
+};
 use itertools::Itertools;
-use pathfinding::prelude::bfs;
 
-pub fn parse_input(input: &str) -> (Vec<Vec<u32>>, (usize, usize), (usize, usize)) {
-    let starting = input.find('S').unwrap();
-    let ending = input.find('E').unwrap();
+pub type Position = (usize, usize);
+pub type Map = Vec<Vec<u32>>;
 
+pub fn parse_input(input: &str) -> (Map, Position, Position) {
     let map = input
         .lines()
         .map(|line| line.chars().collect_vec())
@@ -50,39 +53,37 @@ pub fn parse_input(input: &str) -> (Vec<Vec<u32>>, (usize, usize), (usize, usize
     (map, starting_pos, ending_pos)
 }
 
-pub fn search(
-    map: &Vec<Vec<u32>>,
-    starting: (usize, usize),
-    ending: (usize, usize),
-) -> Option<Vec<(usize, usize)>> {
-    bfs(
-        &starting,
-        |p| {
-            let mut next = vec![];
-
-            if p.0 > 0 && map[p.0 - 1][p.1].checked_sub(map[p.0][p.1]).unwrap_or(0) <= 1 {
-                next.push((p.0 - 1, p.1));
-            }
-
-            if p.0 < map.len() - 1 && map[p.0 + 1][p.1].checked_sub(map[p.0][p.1]).unwrap_or(0) <= 1
-            {
-                next.push((p.0 + 1, p.1));
-            }
-
-            if p.1 > 0 && map[p.0][p.1 - 1].checked_sub(map[p.0][p.1]).unwrap_or(0) <= 1 {
-                next.push((p.0, p.1 - 1));
-            }
-
-            if p.1 < map[0].len() - 1
-                && map[p.0][p.1 + 1].checked_sub(map[p.0][p.1]).unwrap_or(0) <= 1
-            {
-                next.push((p.0, p.1 + 1));
-            }
-
-            next
-        },
-        |p| *p == ending,
-    )
+// All orthogonal neighbours of `p` for which `can_step(from_height, to_height)` holds
+pub fn neighbours(map: &Map, p: &Position, can_step: impl Fn(u32, u32) -> bool) -> Vec<Position> {
+    let mut next = vec![];
+
+    if p.0 > 0 {
+        next.push((p.0 - 1, p.1));
+    }
+
+    if p.0 < map.len() - 1 {
+        next.push((p.0 + 1, p.1));
+    }
+
+    if p.1 > 0 {
+        next.push((p.0, p.1 - 1));
+    }
+
+    if p.1 < map[0].len() - 1 {
+        next.push((p.0, p.1 + 1));
+    }
+
+    next.into_iter()
+        .filter(|n| can_step(map[p.0][p.1], map[n.0][n.1]))
+        .collect()
+}
+
+fn climbable(from: u32, to: u32) -> bool {
+    to.saturating_sub(from) <= 1
+}
+
+pub fn search(map: &Map, starting: Position, ending: Position) -> Option<Vec<Position>> {
+    bfs_multi([starting], |p| neighbours(map, p, climbable)).path(&ending)
 }
 
 pub fn part_one(input: &str) -> Option<u32> {
@@ -95,36 +96,67 @@ pub fn part_one(input: &str) -> Option<u32> {
 pub fn part_two(input: &str) -> Option<u32> {
     let (map, _, ending) = parse_input(input);
 
-    let candidates = map
-        .iter()
-        .enumerate()
-        .flat_map(|(line_num, line)| {
-            line.iter()
-                .enumerate()
-                .filter_map(|(col_num, &height)| {
-                    if height == 0 {
-                        Some((line_num, col_num))
-                    } else {
-                        None
-                    }
-                })
-                .collect_vec()
-        })
-        .collect_vec();
+    // Walk down from the end, so a single search reaches every possible start
+    let distances = bfs_multi([ending], |p| {
+        neighbours(&map, p, |from, to| climbable(to, from))
+    });
 
-    let mut paths = candidates
+    distances
         .iter()
-        .filter_map(|&canidate| search(&map, canidate, ending))
-        .collect_vec();
-    paths.sort_by(|a, b| a.len().cmp(&b.len()));
+        .filter(|(p, _)| map[p.0][p.1] == 0)
+        .map(|(_, steps)| steps as u32)
+        .min()
+}
+
+// Heights use the first 26 colors, the path is drawn on top of them
+fn draw(map: &Map, path: &[Position], ending: Position) -> Frame {
+    let mut frame = Frame::from_fn(map[0].len(), map.len(), |x, y| map[y][x] as u8);
+
+    for p in path {
+        frame.set(p.1, p.0, 26);
+    }
 
-    Some(paths.first().unwrap().len() as u32 - 1)
+    frame.set(ending.1, ending.0, 27);
+    frame
+}
+
+fn render(input: &str, args: &RenderArgs) {
+    if !args.is_enabled() {
+        return;
+    }
+
+    let (map, starting, ending) = parse_input(input);
+    let path = match search(&map, starting, ending) {
+        Some(path) => path,
+        None => {
+            eprintln!("No path to render");
+            return;
+        }
+    };
+
+    let palette = Palette::gradient(BLACK, GREEN, 26).with_colors(&[GOLD, RED]);
+
+    if let Some(path_file) = &args.png {
+        if let Err(e) = write_png(path_file, &draw(&map, &path, ending), &palette, 4) {
+            eprintln!("Failed to write {:?}: {}", path_file, e);
+            return;
+        }
+    }
+
+    if let Some(path_file) = &args.gif {
+        let mut recorder = GifRecorder::new(palette, 4, 3).every(args.every);
+        (1..=path.len()).for_each(|step| recorder.push_with(|| draw(&map, &path[..step], ending)));
+        if let Err(e) = recorder.write(path_file) {
+            eprintln!("Failed to write {:?}: {}", path_file, e);
+        }
+    }
 }
 
 fn main() {
     let input = &advent_of_code::read_file("inputs", 12);
     advent_of_code::solve!(1, part_one, input);
     advent_of_code::solve!(2, part_two, input);
+    render(input, &RenderArgs::from_env());
 }
 
 #[cfg(test)]
@@ -1,5 +1,9 @@
 use std::collections::HashMap;
 
+use advent_of_code::helpers::{
+    animate::{AnimateArgs, Animator},
+    visualize::{write_png, Bounds, Frame, GifRecorder, Palette, RenderArgs, BLACK, GOLD, GRAY},
+};
 use itertools::Itertools;
 
 pub fn parse_input(input: &str) -> Vec<Vec<(i32, i32)>> {
@@ -24,7 +28,9 @@ pub enum Item {
     Sand,
 }
 
-pub fn build_map(lines: &Vec<Vec<(i32, i32)>>) -> HashMap<(i32, i32), Item> {
+pub type Map = HashMap<(i32, i32), Item>;
+
+pub fn build_map(lines: &[Vec<(i32, i32)>]) -> Map {
     lines
         .iter()
         .flat_map(|line| {
@@ -58,7 +64,7 @@ pub fn build_map(lines: &Vec<Vec<(i32, i32)>>) -> HashMap<(i32, i32), Item> {
 }
 
 // The bool in the return value is if the sand fell into the A B Y S S
-fn drop_sand(map: &HashMap<(i32, i32), Item>) -> (HashMap<(i32, i32), Item>, bool) {
+fn drop_sand(map: &Map) -> (Map, bool) {
     let mut new = map.clone();
     let mut sand = (500, 0);
 
@@ -82,10 +88,8 @@ fn drop_sand(map: &HashMap<(i32, i32), Item>) -> (HashMap<(i32, i32), Item>, boo
     (new, true)
 }
 
-pub fn part_one(input: &str) -> Option<u32> {
-    let lines = parse_input(input);
-    let mut map = build_map(&lines);
-
+// Pours sand until a grain falls into the abyss, returns how many grains came to rest
+pub fn fill(mut map: Map, mut on_frame: Option<&mut dyn FnMut(&Map)>) -> u32 {
     let mut amount = 0;
     loop {
         let (new_map, fell) = drop_sand(&map);
@@ -96,12 +100,23 @@ pub fn part_one(input: &str) -> Option<u32> {
         }
 
         amount += 1;
+
+        if let Some(on_frame) = on_frame.as_mut() {
+            on_frame(&map);
+        }
     }
 
-    Some(amount)
+    amount
 }
 
-fn drop_sand_2(map: &HashMap<(i32, i32), Item>, floor: i32) -> HashMap<(i32, i32), Item> {
+pub fn part_one(input: &str) -> Option<u32> {
+    let lines = parse_input(input);
+    let map = build_map(&lines);
+
+    Some(fill(map, None))
+}
+
+fn drop_sand_2(map: &Map, floor: i32) -> Map {
     let mut new = map.clone();
     let mut sand = (500, 0);
 
@@ -126,36 +141,135 @@ fn drop_sand_2(map: &HashMap<(i32, i32), Item>, floor: i32) -> HashMap<(i32, i32
     }
 }
 
-pub fn part_two(input: &str) -> Option<u32> {
-    let lines = parse_input(input);
-    let mut map = build_map(&lines);
-
-    let mut points = map
-        .clone()
-        .into_iter()
-        .map(|(point, _)| point)
-        .collect_vec();
-    points.sort_by(|a, b| a.1.cmp(&b.1));
-
-    let floor = points.last().unwrap().1 + 2;
+fn get_floor(map: &Map) -> i32 {
+    map.keys().map(|point| point.1).max().unwrap() + 2
+}
 
+// Pours sand onto the floor until the source is blocked, returns how many grains came to rest
+// and the final map
+pub fn fill_to_floor(
+    mut map: Map,
+    floor: i32,
+    mut on_frame: Option<&mut dyn FnMut(&Map)>,
+) -> (u32, Map) {
     let mut amount = 0;
     loop {
         map = drop_sand_2(&map, floor);
         amount += 1;
 
+        if let Some(on_frame) = on_frame.as_mut() {
+            on_frame(&map);
+        }
+
         if map.contains_key(&(500, 0)) {
             break;
         }
     }
 
-    Some(amount)
+    (amount, map)
+}
+
+pub fn part_two(input: &str) -> Option<u32> {
+    let lines = parse_input(input);
+    let map = build_map(&lines);
+    let floor = get_floor(&map);
+
+    Some(fill_to_floor(map, floor, None).0)
+}
+
+fn draw(map: &Map, floor: i32, bounds: Bounds) -> Frame {
+    let floor_points = (bounds.min_x..=bounds.max_x).map(|x| ((x, floor as i64), 1));
+    let points = map.iter().map(|(point, item)| {
+        let value = match item {
+            Item::Rock => 1,
+            Item::Sand => 2,
+        };
+
+        ((point.0 as i64, point.1 as i64), value)
+    });
+
+    Frame::from_points_in(bounds, points.chain(floor_points))
+}
+
+fn render(input: &str, args: &RenderArgs) {
+    if !args.is_enabled() {
+        return;
+    }
+
+    let lines = parse_input(input);
+    let map = build_map(&lines);
+    let floor = get_floor(&map);
+
+    // The pile can't spread further than the floor is deep
+    let bounds = Bounds::of(map.keys().map(|point| (point.0 as i64, point.1 as i64)))
+        .unwrap()
+        .include((500, 0))
+        .include((500 - floor as i64, floor as i64))
+        .include((500 + floor as i64, floor as i64));
+
+    let palette = Palette::new(&[BLACK, GRAY, GOLD]);
+    // One frame per grain adds up quickly, so they are only built when recording a GIF
+    let mut recorder = args
+        .gif
+        .as_ref()
+        .map(|_| GifRecorder::new(palette.clone(), 2, 2).every(args.every));
+    let (_, last) = match recorder.as_mut() {
+        Some(recorder) => fill_to_floor(
+            map,
+            floor,
+            Some(&mut |map: &Map| recorder.push_with(|| draw(map, floor, bounds))),
+        ),
+        None => fill_to_floor(map, floor, None),
+    };
+
+    if let Some(path) = &args.png {
+        if let Err(e) = write_png(path, &draw(&last, floor, bounds), &palette, 2) {
+            eprintln!("Failed to write {:?}: {}", path, e);
+            return;
+        }
+    }
+
+    if let (Some(path), Some(recorder)) = (&args.gif, &recorder) {
+        if let Err(e) = recorder.write(path) {
+            eprintln!("Failed to write {:?}: {}", path, e);
+        }
+    }
+}
+
+fn animate(input: &str, args: &AnimateArgs) {
+    if !args.enabled {
+        return;
+    }
+
+    let lines = parse_input(input);
+    let map = build_map(&lines);
+    let floor = get_floor(&map);
+    let mut animator = Animator::new(args.delay);
+
+    fill_to_floor(
+        map,
+        floor,
+        Some(&mut |map: &Map| {
+            let items = map.iter().map(|(point, item)| {
+                let chr = match item {
+                    Item::Rock => '#',
+                    Item::Sand => 'o',
+                };
+
+                ((point.0 as i64, point.1 as i64), chr)
+            });
+
+            animator.draw(items.chain([((500, 0), '+')]));
+        }),
+    );
 }
 
 fn main() {
     let input = &advent_of_code::read_file("inputs", 14);
     advent_of_code::solve!(1, part_one, input);
     advent_of_code::solve!(2, part_two, input);
+    render(input, &RenderArgs::from_env());
+    animate(input, &AnimateArgs::from_env());
 }
 
 #[cfg(test)]
@@ -1,3 +1,6 @@
+use advent_of_code::helpers::visualize::{
+    write_png, Bounds, Frame, GifRecorder, Palette, RenderArgs, BLACK, GOLD, GRAY, RED,
+};
 use itertools::Itertools;
 
 pub type Coordinate = (i64, i64);
@@ -37,7 +40,7 @@ pub fn parse_input(input: &str) -> Vec<Reading> {
         .collect_vec()
 }
 
-pub fn filter_coordinates(readings: &Vec<Reading>, y_filter: i64) -> Vec<Coordinate> {
+pub fn filter_coordinates(readings: &[Reading], y_filter: i64) -> Vec<Coordinate> {
     let beacons = readings.iter().map(|reading| reading.1).collect_vec();
 
     readings
@@ -68,7 +71,7 @@ pub fn filter_coordinates(readings: &Vec<Reading>, y_filter: i64) -> Vec<Coordin
         .collect()
 }
 
-pub fn find_beacon(readings: &Vec<Reading>, max_search_x: i64, max_search_y: i64) -> Coordinate {
+pub fn find_beacon(readings: &[Reading], max_search_x: i64, max_search_y: i64) -> Coordinate {
     let circles = readings
         .iter()
         .map(|reading| {
@@ -110,10 +113,85 @@ pub fn part_two(input: &str) -> Option<i64> {
     Some(coordinate.0 * 4000000 + coordinate.1)
 }
 
+fn distance(reading: &Reading) -> i64 {
+    (reading.0 .0 - reading.1 .0).abs() + (reading.0 .1 - reading.1 .1).abs()
+}
+
+// Real inputs span millions of cells, so every pixel stands for a `step` x `step` square
+fn draw(readings: &[Reading], bounds: Bounds, step: i64) -> Frame {
+    let to_pixel = |point: &Coordinate| {
+        (
+            ((point.0 - bounds.min_x) / step) as usize,
+            ((point.1 - bounds.min_y) / step) as usize,
+        )
+    };
+
+    let mut frame = Frame::from_fn(
+        bounds.width().div_ceil(step as usize),
+        bounds.height().div_ceil(step as usize),
+        |x, y| {
+            let point = (
+                bounds.min_x + x as i64 * step,
+                bounds.min_y + y as i64 * step,
+            );
+            let covered = readings.iter().any(|reading| {
+                (point.0 - reading.0 .0).abs() + (point.1 - reading.0 .1).abs() <= distance(reading)
+            });
+
+            u8::from(covered)
+        },
+    );
+
+    for (sensor, beacon) in readings {
+        let (x, y) = to_pixel(beacon);
+        frame.set(x, y, 2);
+        let (x, y) = to_pixel(sensor);
+        frame.set(x, y, 3);
+    }
+
+    frame
+}
+
+fn render(input: &str, args: &RenderArgs) {
+    if !args.is_enabled() {
+        return;
+    }
+
+    let readings = parse_input(input);
+    let bounds = Bounds::of(readings.iter().flat_map(|reading| {
+        let (x, y) = reading.0;
+        let distance = distance(reading);
+
+        [(x - distance, y - distance), (x + distance, y + distance)]
+    }))
+    .unwrap();
+    let step = (bounds.width().max(bounds.height()) as i64 / 800).max(1);
+    let palette = Palette::new(&[BLACK, GRAY, GOLD, RED]);
+
+    if let Some(path) = &args.png {
+        if let Err(e) = write_png(path, &draw(&readings, bounds, step), &palette, 1) {
+            eprintln!("Failed to write {:?}: {}", path, e);
+            return;
+        }
+    }
+
+    // One frame per sensor that gets added
+    if let Some(path) = &args.gif {
+        let mut recorder = GifRecorder::new(palette, 1, 50).every(args.every);
+        (1..=readings.len()).for_each(|count| {
+            recorder.push_with(|| draw(&readings[..count], bounds, step));
+        });
+        if let Err(e) = recorder.write(path) {
+            eprintln!("Failed to write {:?}: {}", path, e);
+        }
+    }
+}
+
 fn main() {
     let input = &advent_of_code::read_file("inputs", 15);
     advent_of_code::solve!(1, part_one, input);
     advent_of_code::solve!(2, part_two, input);
+    render(input, &RenderArgs::from_env());
 }
 
 #[cfg(test)]
@@ -2,3 +2,8 @@
  * Use this file if you want to extract helpers from your solutions.
  * Example import from this file: `use advent_of_code::helpers::example_fn;`.
  */
+pub mod animate;
+pub mod cycle;
+pub mod search;
+pub mod tree;
+pub mod visualize;
@@ -0,0 +1,213 @@
+/*
+ * Redraws simulations in the terminal. Solutions opt in via `--animate` and `--delay <ms>`,
+ * e.g. `cargo solve 14 -- --animate --delay 20`.
+ */
+use std::{
+    collections::HashMap,
+    env,
+    io::{self, Write},
+    thread,
+    time::Duration,
+};
+
+use crate::{
+    helpers::visualize::Bounds, ANSI_CLEAR, ANSI_HIDE_CURSOR, ANSI_HOME, ANSI_SHOW_CURSOR,
+};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct AnimateArgs {
+    pub enabled: bool,
+    pub delay: Duration,
+}
+
+impl AnimateArgs {
+    /// The animation is turned off if `--delay` has an invalid value.
+    pub fn from_env() -> Self {
+        let mut args = pico_args::Arguments::from_env();
+        let enabled = args.contains("--animate");
+
+        match args.opt_value_from_str("--delay") {
+            Ok(delay) => Self {
+                enabled,
+                delay: Duration::from_millis(delay.unwrap_or(50)),
+            },
+            Err(e) => {
+                if enabled {
+                    eprintln!("Not animating: {}", e);
+                }
+
+                Self {
+                    enabled: false,
+                    delay: Duration::from_millis(50),
+                }
+            }
+        }
+    }
+}
+
+/// Draws frames of points in place. The viewport grows to fit everything drawn so far, and
+/// follows the latest frame once that no longer fits the terminal.
+#[derive(Debug, Clone)]
+pub struct Animator {
+    delay: Duration,
+    columns: usize,
+    rows: usize,
+    bounds: Option<Bounds>,
+}
+
+impl Animator {
+    pub fn new(delay: Duration) -> Self {
+        let size = |var: &str, default: usize| {
+            env::var(var)
+                .ok()
+                .and_then(|value| value.parse().ok())
+                .unwrap_or(default)
+        };
+
+        print!("{}", ANSI_HIDE_CURSOR);
+
+        Self {
+            delay,
+            columns: size("COLUMNS", 80),
+            // Keep a line free for the cursor
+            rows: size("LINES", 24).saturating_sub(1).max(1),
+            bounds: None,
+        }
+    }
+
+    /// Draws `cells` at their coordinates, with `y` growing downwards.
+    pub fn draw(&mut self, cells: impl IntoIterator<Item = ((i64, i64), char)>) {
+        let cells = cells.into_iter().collect::<HashMap<(i64, i64), char>>();
+        let frame_bounds = match Bounds::of(cells.keys().copied()) {
+            Some(bounds) => bounds,
+            None => return self.draw_text(""),
+        };
+
+        let bounds = match self.bounds {
+            Some(bounds) => bounds
+                .include((frame_bounds.min_x, frame_bounds.min_y))
+                .include((frame_bounds.max_x, frame_bounds.max_y)),
+            None => frame_bounds,
+        };
+        self.bounds = Some(bounds);
+
+        let viewport = self.viewport(bounds, frame_bounds);
+        let text = (viewport.min_y..=viewport.max_y)
+            .map(|y| {
+                (viewport.min_x..=viewport.max_x)
+                    .map(|x| *cells.get(&(x, y)).unwrap_or(&' '))
+                    .collect::<String>()
+            })
+            .collect::<Vec<String>>()
+            .join("\n");
+
+        self.draw_text(&text);
+    }
+
+    /// Draws pre-rendered text, which may contain ANSI escape sequences.
+    pub fn draw_text(&mut self, text: &str) {
+        let mut stdout = io::stdout().lock();
+        write!(stdout, "{}{}{}", ANSI_HOME, ANSI_CLEAR, text).unwrap();
+        writeln!(stdout).unwrap();
+        stdout.flush().unwrap();
+
+        thread::sleep(self.delay);
+    }
+
+    // Fits the terminal around the latest frame, staying within everything drawn so far
+    fn viewport(&self, bounds: Bounds, frame: Bounds) -> Bounds {
+        let fit = |min: i64, max: i64, frame_min: i64, frame_max: i64, size: usize| {
+            let size = size as i64;
+
+            if max - min < size {
+                return (min, max);
+            }
+
+            let center = (frame_min + frame_max) / 2;
+            let start = (center - size / 2).clamp(min, max - size + 1);
+
+            (start, start + size - 1)
+        };
+
+        let (min_x, max_x) = fit(
+            bounds.min_x,
+            bounds.max_x,
+            frame.min_x,
+            frame.max_x,
+            self.columns,
+        );
+        let (min_y, max_y) = fit(
+            bounds.min_y,
+            bounds.max_y,
+            frame.min_y,
+            frame.max_y,
+            self.rows,
+        );
+
+        Bounds {
+            min_x,
+            min_y,
+            max_x,
+            max_y,
+        }
+    }
+}
+
+impl Drop for Animator {
+    fn drop(&mut self) {
+        print!("{}", ANSI_SHOW_CURSOR);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_viewport() {
+        let animator = Animator {
+            delay: Duration::ZERO,
+            columns: 10,
+            rows: 4,
+            bounds: None,
+        };
+        let bounds = Bounds {
+            min_x: 0,
+            min_y: 0,
+            max_x: 99,
+            max_y: 3,
+        };
+        let frame = Bounds {
+            min_x: 50,
+            min_y: 1,
+            max_x: 50,
+            max_y: 1,
+        };
+
+        assert_eq!(
+            animator.viewport(bounds, frame),
+            Bounds {
+                min_x: 45,
+                min_y: 0,
+                max_x: 54,
+                max_y: 3
+            }
+        );
+        assert_eq!(
+            animator.viewport(
+                bounds,
+                Bounds {
+                    min_x: 98,
+                    max_x: 99,
+                    ..frame
+                }
+            ),
+            Bounds {
+                min_x: 90,
+                min_y: 0,
+                max_x: 99,
+                max_y: 3
+            }
+        );
+    }
+}
@@ -0,0 +1,204 @@
+use std::ops::{Add, Mul, Sub};
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+pub struct Cycle {
+    // The first step that is part of the repeating section
+    pub start: usize,
+    // How many steps it takes for a state to repeat
+    pub length: usize,
+}
+
+impl Cycle {
+    /// Maps `step` onto the equivalent step of the first repetition, together with the number of
+    /// full cycles that were skipped to get there.
+    pub fn reduce(&self, step: usize) -> (usize, usize) {
+        if step < self.start {
+            return (step, 0);
+        }
+
+        let offset = step - self.start;
+
+        (self.start + offset % self.length, offset / self.length)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct History<M> {
+    // The metric of every state that was simulated, starting with the initial one
+    pub metrics: Vec<M>,
+    pub cycle: Option<Cycle>,
+}
+
+impl<M> History<M> {
+    /// Returns `value` of the metric after `step` steps, or `None` if no cycle was found and
+    /// `step` is past the simulated states.
+    ///
+    /// Once a cycle was found, the value is expected to grow by the same amount on every
+    /// repetition (e.g. a running counter or the height of a tower), which lets us skip ahead
+    /// without simulating the remaining steps.
+    pub fn extrapolate<T>(&self, step: usize, value: impl Fn(&M) -> T) -> Option<T>
+    where
+        T: Copy + Add<Output = T> + Sub<Output = T> + Mul<Output = T> + TryFrom<usize>,
+    {
+        let cycle = match self.cycle {
+            Some(cycle) => cycle,
+            None => return self.metrics.get(step).map(value),
+        };
+
+        let (index, repetitions) = cycle.reduce(step);
+        let growth =
+            value(&self.metrics[cycle.start + cycle.length]) - value(&self.metrics[cycle.start]);
+
+        Some(value(&self.metrics[index]) + growth * T::try_from(repetitions).ok()?)
+    }
+}
+
+/// Runs `step` until a state repeats or `limit` steps have been taken, recording `metric` for
+/// every state.
+///
+/// `same` compares two states by everything that influences future steps, while leaving out
+/// accumulated values such as counters. Uses Brent's algorithm, so apart from the metrics only a
+/// couple of states are kept at any time. Finding where the cycle starts simulates the steps up
+/// to it once more.
+pub fn find_cycle<S, M>(
+    initial: S,
+    same: impl Fn(&S, &S) -> bool,
+    mut step: impl FnMut(&S) -> S,
+    metric: impl Fn(&S) -> M,
+    limit: usize,
+) -> History<M>
+where
+    S: Clone,
+{
+    let mut metrics = vec![metric(&initial)];
+
+    // The tortoise waits at every power of two while the hare runs ahead, until they meet
+    let mut tortoise = initial.clone();
+    let mut hare = initial.clone();
+    let mut power = 1;
+    let mut length = 0;
+
+    loop {
+        if metrics.len() > limit {
+            return History {
+                metrics,
+                cycle: None,
+            };
+        }
+
+        hare = step(&hare);
+        metrics.push(metric(&hare));
+        length += 1;
+
+        if same(&tortoise, &hare) {
+            break;
+        }
+
+        if length == power {
+            tortoise = hare.clone();
+            power *= 2;
+            length = 0;
+        }
+    }
+
+    // Keeping them `length` steps apart, both meet at the start of the cycle
+    let mut tortoise = initial;
+    let mut hare = (0..length).fold(tortoise.clone(), |state, _| step(&state));
+    let mut start = 0;
+
+    while !same(&tortoise, &hare) {
+        tortoise = step(&tortoise);
+        hare = step(&hare);
+        start += 1;
+    }
+
+    History {
+        metrics,
+        cycle: Some(Cycle { start, length }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_cycle() {
+        // 3 -> 9 -> 27 % 10 = 7 -> 21 % 10 = 1 -> 3 -> ...
+        let history = find_cycle(3, |a, b| a == b, |state| state * 3 % 10, |_| (), 100);
+
+        assert_eq!(
+            history.cycle,
+            Some(Cycle {
+                start: 0,
+                length: 4
+            })
+        );
+    }
+
+    #[test]
+    fn test_long_tail() {
+        // Counts up to 20, then loops through 20..27
+        let history = find_cycle(
+            0,
+            |a, b| a == b,
+            |state| {
+                if *state < 20 {
+                    state + 1
+                } else {
+                    20 + (state - 19) % 7
+                }
+            },
+            |state| *state,
+            1000,
+        );
+
+        assert_eq!(
+            history.cycle,
+            Some(Cycle {
+                start: 20,
+                length: 7
+            })
+        );
+        assert_eq!(history.extrapolate(29, |state| *state), Some(22));
+    }
+
+    #[test]
+    fn test_extrapolate() {
+        // (value, running sum of values), the value repeats as 1, 2, 0, 2, 0, ...
+        let history = find_cycle(
+            (1_u64, 1_u64),
+            |a, b| a.0 == b.0,
+            |state| {
+                let next = if state.0 == 1 { 2 } else { 2 - state.0 };
+                (next, state.1 + next)
+            },
+            |state| *state,
+            100,
+        );
+
+        assert_eq!(
+            history.cycle,
+            Some(Cycle {
+                start: 1,
+                length: 2
+            })
+        );
+        assert_eq!(history.extrapolate(0, |state| state.1), Some(1));
+        assert_eq!(history.extrapolate(4, |state| state.1), Some(5));
+        assert_eq!(
+            history.extrapolate(1_000_000_000_000, |state| state.1),
+            Some(1_000_000_000_001)
+        );
+    }
+
+    #[test]
+    fn test_no_cycle() {
+        let history = find_cycle(0, |a, b| a == b, |state| state + 1, |state| *state, 10);
+
+        assert_eq!(history.cycle, None);
+        assert_eq!(history.metrics.len(), 11);
+        assert_eq!(history.extrapolate(10, |state| *state), Some(10));
+        assert_eq!(history.extrapolate(11, |state| *state), None);
+    }
+}
@@ -0,0 +1,196 @@
+/*
+ * Search helpers that start from several nodes at once. To search backwards from a goal, pass the
+ * goal as the only start together with a `successors` closure that follows the edges in reverse.
+ */
+use std::{
+    collections::{HashMap, VecDeque},
+    hash::Hash,
+};
+
+use pathfinding::{
+    num_traits::Zero,
+    prelude::{astar, dijkstra_all},
+};
+
+#[derive(Debug, Clone)]
+pub struct Distances<N, C> {
+    // Every reached node, the node it was reached from (`None` for the starts) and its cost
+    reached: HashMap<N, (Option<N>, C)>,
+}
+
+impl<N, C> Distances<N, C>
+where
+    N: Eq + Hash + Clone,
+    C: Copy,
+{
+    pub fn cost(&self, node: &N) -> Option<C> {
+        self.reached.get(node).map(|(_, cost)| *cost)
+    }
+
+    /// Path from the closest start to `node`, including both.
+    pub fn path(&self, node: &N) -> Option<Vec<N>> {
+        let mut current = node;
+        let mut path = vec![current.clone()];
+
+        while let (Some(parent), _) = self.reached.get(current)? {
+            path.push(parent.clone());
+            current = parent;
+        }
+
+        path.reverse();
+        Some(path)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&N, C)> {
+        self.reached.iter().map(|(node, (_, cost))| (node, *cost))
+    }
+
+    pub fn len(&self) -> usize {
+        self.reached.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.reached.is_empty()
+    }
+}
+
+/// Breadth-first search from all `starts` at once, returning the number of steps to every
+/// reachable node.
+pub fn bfs_multi<N, FN, IN>(
+    starts: impl IntoIterator<Item = N>,
+    mut successors: FN,
+) -> Distances<N, usize>
+where
+    N: Eq + Hash + Clone,
+    FN: FnMut(&N) -> IN,
+    IN: IntoIterator<Item = N>,
+{
+    let mut reached = HashMap::new();
+    let mut queue = VecDeque::new();
+
+    for start in starts {
+        if !reached.contains_key(&start) {
+            reached.insert(start.clone(), (None, 0));
+            queue.push_back((start, 0));
+        }
+    }
+
+    while let Some((node, steps)) = queue.pop_front() {
+        for next in successors(&node) {
+            if !reached.contains_key(&next) {
+                reached.insert(next.clone(), (Some(node.clone()), steps + 1));
+                queue.push_back((next, steps + 1));
+            }
+        }
+    }
+
+    Distances { reached }
+}
+
+/// Dijkstra from all `starts` at once, returning the lowest cost to every reachable node.
+pub fn dijkstra_multi<N, C, FN, IN>(
+    starts: impl IntoIterator<Item = N>,
+    mut successors: FN,
+) -> Distances<N, C>
+where
+    N: Eq + Hash + Clone,
+    C: Zero + Ord + Copy,
+    FN: FnMut(&N) -> IN,
+    IN: IntoIterator<Item = (N, C)>,
+{
+    let starts = starts.into_iter().collect::<Vec<N>>();
+
+    // `None` is a virtual node that leads to every start for free
+    let reached = dijkstra_all(&None, |node: &Option<N>| match node {
+        None => starts
+            .iter()
+            .map(|start| (Some(start.clone()), C::zero()))
+            .collect::<Vec<_>>(),
+        Some(node) => successors(node)
+            .into_iter()
+            .map(|(next, cost)| (Some(next), cost))
+            .collect(),
+    });
+
+    Distances {
+        reached: reached
+            .into_iter()
+            .filter_map(|(node, (parent, cost))| node.map(|node| (node, (parent, cost))))
+            .collect(),
+    }
+}
+
+/// A* from all `starts` at once to the first node that satisfies `success`.
+pub fn astar_multi<N, C, FN, IN, FH, FS>(
+    starts: impl IntoIterator<Item = N>,
+    mut successors: FN,
+    mut heuristic: FH,
+    mut success: FS,
+) -> Option<(Vec<N>, C)>
+where
+    N: Eq + Hash + Clone,
+    C: Zero + Ord + Copy,
+    FN: FnMut(&N) -> IN,
+    IN: IntoIterator<Item = (N, C)>,
+    FH: FnMut(&N) -> C,
+    FS: FnMut(&N) -> bool,
+{
+    let starts = starts.into_iter().collect::<Vec<N>>();
+
+    let (path, cost) = astar(
+        &None,
+        |node: &Option<N>| match node {
+            None => starts
+                .iter()
+                .map(|start| (Some(start.clone()), C::zero()))
+                .collect::<Vec<_>>(),
+            Some(node) => successors(node)
+                .into_iter()
+                .map(|(next, cost)| (Some(next), cost))
+                .collect(),
+        },
+        |node| node.as_ref().map_or(C::zero(), &mut heuristic),
+        |node| node.as_ref().is_some_and(&mut success),
+    )?;
+
+    Some((path.into_iter().flatten().collect(), cost))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 0 -> 1 -> 2 -> 3 -> 4 -> 5 -> ... on a line, with 10 as a shortcut to 5
+    fn successors(n: &u32) -> Vec<u32> {
+        if *n == 10 {
+            vec![5]
+        } else if *n < 8 {
+            vec![n + 1]
+        } else {
+            vec![]
+        }
+    }
+
+    #[test]
+    fn test_bfs_multi() {
+        let distances = bfs_multi([0, 10], successors);
+
+        assert_eq!(distances.cost(&3), Some(3));
+        assert_eq!(distances.cost(&7), Some(3));
+        assert_eq!(distances.path(&7), Some(vec![10, 5, 6, 7]));
+        assert_eq!(distances.cost(&9), None);
+    }
+
+    #[test]
+    fn test_dijkstra_and_astar_multi() {
+        let weighted = |n: &u32| successors(n).into_iter().map(|next| (next, next));
+        let distances = dijkstra_multi([0, 10], weighted);
+
+        assert_eq!(distances.cost(&6), Some(11));
+        assert_eq!(distances.path(&6), Some(vec![10, 5, 6]));
+        assert_eq!(
+            astar_multi([0, 10], weighted, |_| 0, |n| *n == 6),
+            Some((vec![10, 5, 6], 11))
+        );
+    }
+}
@@ -0,0 +1,156 @@
+pub type NodeId = usize;
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone)]
+pub struct Node<T> {
+    pub name: String,
+    pub value: T,
+    pub parent: Option<NodeId>,
+    pub children: Vec<NodeId>,
+}
+
+/// A tree that keeps all of its nodes in one `Vec` and links them by index.
+///
+/// Children are always pushed after their parent, so walking the ids backwards visits every
+/// node after all of its descendants.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone)]
+pub struct Tree<T> {
+    nodes: Vec<Node<T>>,
+}
+
+impl<T> Tree<T> {
+    pub fn new(root_name: &str, root_value: T) -> Self {
+        Self {
+            nodes: vec![Node {
+                name: root_name.to_string(),
+                value: root_value,
+                parent: None,
+                children: vec![],
+            }],
+        }
+    }
+
+    pub fn root(&self) -> NodeId {
+        0
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub fn get(&self, id: NodeId) -> &Node<T> {
+        &self.nodes[id]
+    }
+
+    pub fn get_mut(&mut self, id: NodeId) -> &mut Node<T> {
+        &mut self.nodes[id]
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (NodeId, &Node<T>)> {
+        self.nodes.iter().enumerate()
+    }
+
+    pub fn parent(&self, id: NodeId) -> Option<NodeId> {
+        self.nodes[id].parent
+    }
+
+    pub fn children(&self, id: NodeId) -> &[NodeId] {
+        &self.nodes[id].children
+    }
+
+    pub fn child(&self, id: NodeId, name: &str) -> Option<NodeId> {
+        self.nodes[id]
+            .children
+            .iter()
+            .copied()
+            .find(|&child| self.nodes[child].name == name)
+    }
+
+    pub fn add_child(&mut self, parent: NodeId, name: &str, value: T) -> NodeId {
+        let id = self.nodes.len();
+
+        self.nodes.push(Node {
+            name: name.to_string(),
+            value,
+            parent: Some(parent),
+            children: vec![],
+        });
+        self.nodes[parent].children.push(id);
+
+        id
+    }
+
+    /// Names of all nodes from the root down to `id`, including both.
+    pub fn path(&self, id: NodeId) -> Vec<&str> {
+        let mut path = vec![];
+        let mut current = Some(id);
+
+        while let Some(id) = current {
+            path.push(self.nodes[id].name.as_str());
+            current = self.nodes[id].parent;
+        }
+
+        path.reverse();
+        path
+    }
+
+    /// Computes a value for every node out of the node itself and the values of its children,
+    /// visiting children before their parents. The result is indexed by `NodeId`.
+    pub fn aggregate<A>(&self, mut f: impl FnMut(&Node<T>, &[&A]) -> A) -> Vec<A> {
+        let mut results: Vec<Option<A>> = (0..self.nodes.len()).map(|_| None).collect();
+
+        for id in (0..self.nodes.len()).rev() {
+            let (_, descendants) = results.split_at(id + 1);
+            let children = self.nodes[id]
+                .children
+                .iter()
+                .map(|&child| descendants[child - id - 1].as_ref().unwrap())
+                .collect::<Vec<&A>>();
+
+            results[id] = Some(f(&self.nodes[id], &children));
+        }
+
+        results.into_iter().map(|result| result.unwrap()).collect()
+    }
+
+    /// Sum of `weight` over every subtree, indexed by `NodeId`.
+    pub fn subtree_sums(&self, weight: impl Fn(&T) -> u64) -> Vec<u64> {
+        self.aggregate(|node, children| weight(&node.value) + children.iter().copied().sum::<u64>())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn example() -> Tree<u64> {
+        let mut tree = Tree::new("/", 0);
+        let a = tree.add_child(tree.root(), "a", 0);
+        tree.add_child(a, "b", 3);
+        tree.add_child(a, "c", 4);
+        tree.add_child(tree.root(), "d", 5);
+
+        tree
+    }
+
+    #[test]
+    fn test_lookup() {
+        let tree = example();
+        let a = tree.child(tree.root(), "a").unwrap();
+        let c = tree.child(a, "c").unwrap();
+
+        assert_eq!(tree.child(tree.root(), "c"), None);
+        assert_eq!(tree.parent(c), Some(a));
+        assert_eq!(tree.path(c), vec!["/", "a", "c"]);
+    }
+
+    #[test]
+    fn test_subtree_sums() {
+        let tree = example();
+
+        assert_eq!(tree.subtree_sums(|value| *value), vec![12, 7, 3, 4, 5]);
+    }
+}
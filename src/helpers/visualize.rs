@@ -0,0 +1,447 @@
+/*
+ * Renders grids and point sets to PNG and PGM images and animated GIFs. Solutions opt in via
+ * `--png <path>`, `--pgm <path>`, `--gif <path>` and `--every <n>`, e.g. `cargo solve 14 -- --gif 14.gif`.
+ */
+use std::{
+    borrow::Cow,
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+pub type Color = [u8; 3];
+
+pub const BLACK: Color = [0x0f, 0x0f, 0x23];
+pub const WHITE: Color = [0xcc, 0xcc, 0xcc];
+pub const GOLD: Color = [0xff, 0xff, 0x66];
+pub const GREEN: Color = [0x00, 0x99, 0x00];
+pub const RED: Color = [0xff, 0x00, 0x00];
+pub const GRAY: Color = [0x66, 0x66, 0x66];
+
+/// Colors used by a `Frame`, a cell with value `n` is drawn with the `n`th color. The first
+/// color is the background.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Palette {
+    colors: Vec<Color>,
+}
+
+impl Palette {
+    pub fn new(colors: &[Color]) -> Self {
+        assert!(
+            !colors.is_empty() && colors.len() <= 256,
+            "a palette needs between 1 and 256 colors"
+        );
+
+        Self {
+            colors: colors.to_vec(),
+        }
+    }
+
+    /// `steps` shades between `from` and `to`, handy for height maps.
+    pub fn gradient(from: Color, to: Color, steps: usize) -> Self {
+        let colors = (0..steps)
+            .map(|step| {
+                let t = step as f64 / (steps.max(2) - 1) as f64;
+                let mix = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * t).round() as u8;
+
+                [
+                    mix(from[0], to[0]),
+                    mix(from[1], to[1]),
+                    mix(from[2], to[2]),
+                ]
+            })
+            .collect::<Vec<Color>>();
+
+        Self::new(&colors)
+    }
+
+    /// Appends `colors` after the existing ones.
+    pub fn with_colors(mut self, colors: &[Color]) -> Self {
+        self.colors.extend_from_slice(colors);
+        Self::new(&self.colors)
+    }
+
+    pub fn len(&self) -> usize {
+        self.colors.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.colors.is_empty()
+    }
+
+    pub fn color(&self, value: u8) -> Color {
+        self.colors[value as usize % self.colors.len()]
+    }
+
+    /// Perceived brightness of the `n`th color.
+    pub fn gray(&self, value: u8) -> u8 {
+        let [r, g, b] = self.color(value);
+
+        ((r as u32 * 299 + g as u32 * 587 + b as u32 * 114) / 1000) as u8
+    }
+
+    // Flat RGB triplets, as expected by GIF color tables
+    fn to_rgb(&self) -> Vec<u8> {
+        self.colors.iter().flatten().copied().collect()
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+pub struct Bounds {
+    pub min_x: i64,
+    pub min_y: i64,
+    pub max_x: i64,
+    pub max_y: i64,
+}
+
+impl Bounds {
+    /// Smallest bounds that contain all `points`, or `None` if there are none.
+    pub fn of(points: impl IntoIterator<Item = (i64, i64)>) -> Option<Self> {
+        points.into_iter().fold(None, |bounds, point| {
+            Some(match bounds {
+                None => Bounds {
+                    min_x: point.0,
+                    min_y: point.1,
+                    max_x: point.0,
+                    max_y: point.1,
+                },
+                Some(bounds) => bounds.include(point),
+            })
+        })
+    }
+
+    pub fn include(&self, point: (i64, i64)) -> Self {
+        Bounds {
+            min_x: self.min_x.min(point.0),
+            min_y: self.min_y.min(point.1),
+            max_x: self.max_x.max(point.0),
+            max_y: self.max_y.max(point.1),
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        (self.max_x - self.min_x + 1) as usize
+    }
+
+    pub fn height(&self) -> usize {
+        (self.max_y - self.min_y + 1) as usize
+    }
+
+    pub fn contains(&self, point: (i64, i64)) -> bool {
+        (self.min_x..=self.max_x).contains(&point.0) && (self.min_y..=self.max_y).contains(&point.1)
+    }
+}
+
+/// A grid of palette indices.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Frame {
+    pub width: usize,
+    pub height: usize,
+    cells: Vec<u8>,
+}
+
+impl Frame {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            cells: vec![0; width * height],
+        }
+    }
+
+    pub fn from_fn(width: usize, height: usize, f: impl Fn(usize, usize) -> u8) -> Self {
+        Self {
+            width,
+            height,
+            cells: (0..height)
+                .flat_map(|y| (0..width).map(move |x| (x, y)))
+                .map(|(x, y)| f(x, y))
+                .collect(),
+        }
+    }
+
+    /// Draws `points` with their palette index, sized to fit all of them.
+    pub fn from_points(points: impl IntoIterator<Item = ((i64, i64), u8)>) -> Self {
+        let points = points.into_iter().collect::<Vec<((i64, i64), u8)>>();
+
+        match Bounds::of(points.iter().map(|(point, _)| *point)) {
+            Some(bounds) => Self::from_points_in(bounds, points),
+            None => Self::new(0, 0),
+        }
+    }
+
+    /// Draws `points` with their palette index within fixed `bounds`, points outside of them
+    /// are dropped. Use this for animations so every frame has the same size.
+    pub fn from_points_in(
+        bounds: Bounds,
+        points: impl IntoIterator<Item = ((i64, i64), u8)>,
+    ) -> Self {
+        let mut frame = Self::new(bounds.width(), bounds.height());
+
+        for (point, value) in points {
+            if bounds.contains(point) {
+                frame.set(
+                    (point.0 - bounds.min_x) as usize,
+                    (point.1 - bounds.min_y) as usize,
+                    value,
+                );
+            }
+        }
+
+        frame
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> u8 {
+        self.cells[y * self.width + x]
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, value: u8) {
+        self.cells[y * self.width + x] = value;
+    }
+
+    // Every cell becomes a `scale` x `scale` block of pixels
+    fn scaled(&self, scale: usize, width: usize, height: usize) -> Vec<u8> {
+        (0..height * scale)
+            .flat_map(|y| (0..width * scale).map(move |x| (x / scale, y / scale)))
+            .map(|(x, y)| {
+                if x < self.width && y < self.height {
+                    self.get(x, y)
+                } else {
+                    0
+                }
+            })
+            .collect()
+    }
+}
+
+pub fn write_png(path: &Path, frame: &Frame, palette: &Palette, scale: usize) -> io::Result<()> {
+    let file = File::create(path)?;
+    let mut encoder = png::Encoder::new(
+        BufWriter::new(file),
+        (frame.width * scale) as u32,
+        (frame.height * scale) as u32,
+    );
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+
+    let pixels = frame
+        .scaled(scale, frame.width, frame.height)
+        .iter()
+        .flat_map(|&value| palette.color(value))
+        .collect::<Vec<u8>>();
+
+    encoder.write_header()?.write_image_data(&pixels)?;
+
+    Ok(())
+}
+
+/// Writes a binary PGM, every color of the palette becomes its brightness.
+pub fn write_pgm(path: &Path, frame: &Frame, palette: &Palette, scale: usize) -> io::Result<()> {
+    let mut file = BufWriter::new(File::create(path)?);
+    write!(
+        file,
+        "P5\n{} {}\n255\n",
+        frame.width * scale,
+        frame.height * scale
+    )?;
+
+    let pixels = frame
+        .scaled(scale, frame.width, frame.height)
+        .iter()
+        .map(|&value| palette.gray(value))
+        .collect::<Vec<u8>>();
+    file.write_all(&pixels)?;
+
+    file.flush()
+}
+
+/// Collects frames of a simulation and writes them as an animated GIF.
+#[derive(Debug, Clone)]
+pub struct GifRecorder {
+    palette: Palette,
+    scale: usize,
+    // Delay between frames, in hundredths of a second
+    delay: u16,
+    // Only every nth frame that is pushed gets recorded
+    every: usize,
+    pushed: usize,
+    frames: Vec<Frame>,
+}
+
+impl GifRecorder {
+    pub fn new(palette: Palette, scale: usize, delay: u16) -> Self {
+        Self {
+            palette,
+            scale,
+            delay,
+            every: 1,
+            pushed: 0,
+            frames: vec![],
+        }
+    }
+
+    pub fn every(mut self, every: usize) -> Self {
+        self.every = every.max(1);
+        self
+    }
+
+    pub fn push(&mut self, frame: Frame) {
+        if self.pushed.is_multiple_of(self.every) {
+            self.frames.push(frame);
+        }
+
+        self.pushed += 1;
+    }
+
+    /// Like `push`, but only builds the frame if it is going to be recorded.
+    pub fn push_with(&mut self, frame: impl FnOnce() -> Frame) {
+        if self.pushed.is_multiple_of(self.every) {
+            self.frames.push(frame());
+        }
+
+        self.pushed += 1;
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// Writes all recorded frames, smaller frames are padded with the background color.
+    pub fn write(&self, path: &Path) -> io::Result<()> {
+        let width = self
+            .frames
+            .iter()
+            .map(|frame| frame.width)
+            .max()
+            .unwrap_or(0);
+        let height = self
+            .frames
+            .iter()
+            .map(|frame| frame.height)
+            .max()
+            .unwrap_or(0);
+
+        let file = File::create(path)?;
+        let mut encoder = gif::Encoder::new(
+            BufWriter::new(file),
+            (width * self.scale) as u16,
+            (height * self.scale) as u16,
+            &self.palette.to_rgb(),
+        )
+        .map_err(io::Error::other)?;
+        encoder
+            .set_repeat(gif::Repeat::Infinite)
+            .map_err(io::Error::other)?;
+
+        for frame in &self.frames {
+            let gif_frame = gif::Frame {
+                width: (width * self.scale) as u16,
+                height: (height * self.scale) as u16,
+                delay: self.delay,
+                buffer: Cow::Owned(frame.scaled(self.scale, width, height)),
+                ..gif::Frame::default()
+            };
+
+            encoder.write_frame(&gif_frame).map_err(io::Error::other)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Output options for solutions that can be rendered, read from the command line.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct RenderArgs {
+    pub png: Option<PathBuf>,
+    pub pgm: Option<PathBuf>,
+    pub gif: Option<PathBuf>,
+    pub every: usize,
+}
+
+impl RenderArgs {
+    /// Rendering is turned off if any of the options has an invalid value.
+    pub fn from_env() -> Self {
+        Self::parse(&mut pico_args::Arguments::from_env()).unwrap_or_else(|e| {
+            eprintln!("Not rendering: {}", e);
+            Self::default()
+        })
+    }
+
+    fn parse(args: &mut pico_args::Arguments) -> Result<Self, pico_args::Error> {
+        Ok(Self {
+            png: args.opt_value_from_str("--png")?,
+            pgm: args.opt_value_from_str("--pgm")?,
+            gif: args.opt_value_from_str("--gif")?,
+            every: args.opt_value_from_str("--every")?.unwrap_or(1),
+        })
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.png.is_some() || self.pgm.is_some() || self.gif.is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_points() {
+        let frame = Frame::from_points([((-1, 5), 1), ((1, 6), 2)]);
+
+        assert_eq!((frame.width, frame.height), (3, 2));
+        assert_eq!(frame.get(0, 0), 1);
+        assert_eq!(frame.get(2, 1), 2);
+        assert_eq!(frame.get(1, 0), 0);
+    }
+
+    #[test]
+    fn test_recorder_every() {
+        let mut recorder = GifRecorder::new(Palette::new(&[BLACK, WHITE]), 1, 10).every(3);
+        (0..7).for_each(|_| recorder.push(Frame::new(1, 1)));
+
+        assert_eq!(recorder.len(), 3);
+    }
+
+    #[test]
+    fn test_write_pgm() {
+        let path = std::env::temp_dir().join("advent_of_code_test.pgm");
+        let frame = Frame::from_fn(2, 1, |x, _| x as u8);
+        write_pgm(
+            &path,
+            &frame,
+            &Palette::new(&[[0, 0, 0], [255, 255, 255]]),
+            1,
+        )
+        .unwrap();
+
+        assert_eq!(std::fs::read(&path).unwrap(), b"P5\n2 1\n255\n\x00\xff");
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_render_args() {
+        let parse = |args: &[&str]| {
+            RenderArgs::parse(&mut pico_args::Arguments::from_vec(
+                args.iter().map(|arg| arg.into()).collect(),
+            ))
+        };
+
+        assert_eq!(parse(&["--gif", "a.gif"]).unwrap().every, 1);
+        assert!(parse(&["--gif", "a.gif", "--every", "x"]).is_err());
+    }
+
+    #[test]
+    fn test_scaled() {
+        let frame = Frame::from_fn(2, 1, |x, _| x as u8);
+
+        assert_eq!(
+            frame.scaled(2, 3, 1),
+            vec![0, 0, 1, 1, 0, 0, 0, 0, 1, 1, 0, 0]
+        );
+    }
+}
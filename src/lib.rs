@@ -11,6 +11,10 @@ pub mod helpers;
 pub const ANSI_ITALIC: &str = "\x1b[3m";
 pub const ANSI_BOLD: &str = "\x1b[1m";
 pub const ANSI_RESET: &str = "\x1b[0m";
+pub const ANSI_CLEAR: &str = "\x1b[2J";
+pub const ANSI_HOME: &str = "\x1b[H";
+pub const ANSI_HIDE_CURSOR: &str = "\x1b[?25l";
+pub const ANSI_SHOW_CURSOR: &str = "\x1b[?25h";
 
 #[macro_export]
 macro_rules! solve {